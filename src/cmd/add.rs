@@ -1,9 +1,14 @@
 use crate::cmd::common::{load_aliases, load_vault};
+use crate::crypto::generate::{generate_secret, DEFAULT_SPEC};
+use crate::crypto::otp::parse_seed;
 use crate::util::errors::{ObscuraError, ObscuraResult};
-use crate::util::io::{prompt_secret_value, prompt_yes_no};
+use crate::util::io::{prompt_secret_value, prompt_yes_no, read_secret_file};
 use crate::vault::file::{encrypt_and_save_vault, vault_exists};
 use crate::vault::manager::{VaultManager, VaultType};
+use crate::vault::model::AliasKind;
+use crate::vault::namespace::validate_alias;
 use clap::Args;
+use std::path::PathBuf;
 
 #[derive(Args)]
 pub struct AddArgs {
@@ -21,18 +26,60 @@ pub struct AddArgs {
         help = "Copy the alias from the global vault into the project vault"
     )]
     pub from_global: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "from_global",
+        help = "Store the raw contents of a file (certificates, keystores, key files)"
+    )]
+    pub from_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["from_global", "from_file"],
+        help = "Store the value as an OTP seed (otpauth:// URI or base32 secret)"
+    )]
+    pub otp: bool,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_SPEC,
+        conflicts_with_all = ["from_global", "from_file", "otp"],
+        help = "Generate a random value instead of prompting (see 'obscura generate')"
+    )]
+    pub generate: Option<String>,
+
+    #[arg(
+        long = "tag",
+        value_name = "TAG",
+        value_delimiter = ',',
+        help = "Tag the alias (repeatable or comma separated)"
+    )]
+    pub tags: Vec<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["from_global", "from_file", "otp"],
+        help = "Expand ${...} references to other aliases when the value is read"
+    )]
+    pub interpolate: bool,
 }
 
 pub fn handle_add(args: AddArgs) -> ObscuraResult<()> {
     if args.from_global {
         add_from_global(&args.alias)
     } else {
-        add_new_secret(&args.alias, args.global, args.project)
+        add_new_secret(&args)
     }
 }
 
-fn add_new_secret(alias: &str, force_global: bool, force_project: bool) -> ObscuraResult<()> {
-    let vault_info = VaultManager::resolve_vault(force_global, force_project)?;
+fn add_new_secret(args: &AddArgs) -> ObscuraResult<()> {
+    let alias = args.alias.as_str();
+    validate_alias(alias)?;
+    let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
 
     if !vault_exists(&vault_info.path) {
         match vault_info.vault_type {
@@ -44,17 +91,35 @@ fn add_new_secret(alias: &str, force_global: bool, force_project: bool) -> Obscu
         }
     }
 
-    let value = prompt_secret_value(alias)?;
+    let (value, kind) = match (&args.from_file, &args.generate) {
+        (Some(path), _) => (read_secret_file(path)?, AliasKind::File),
+        (None, Some(spec)) => (generate_secret(spec)?.into_bytes(), AliasKind::Text),
+        (None, None) => (prompt_secret_value(alias)?.into_bytes(), AliasKind::Text),
+    };
+    let otp_seed = if args.otp {
+        let input = String::from_utf8(value.clone())
+            .map_err(|_| ObscuraError::CustomError("OTP seed must be text".to_string()))?;
+        Some(parse_seed(&input)?)
+    } else {
+        None
+    };
     let (dek, mut aliases_data, vault_file) = load_vault(&vault_info.path)?;
 
-    if aliases_data.aliases.contains_key(alias) {
-        if !prompt_yes_no(&format!("Alias '{}' already exists. Overwrite?", alias))? {
-            println!("Cancelled");
-            return Ok(());
-        }
+    if aliases_data.aliases.contains_key(alias)
+        && !prompt_yes_no(&format!("Alias '{}' already exists. Overwrite?", alias))?
+    {
+        println!("Cancelled");
+        return Ok(());
     }
 
-    aliases_data.add_alias(alias.to_string(), value, &dek)?;
+    match otp_seed {
+        Some(seed) => {
+            aliases_data.add_otp_alias(alias.to_string(), seed.secret, seed.params, &dek)?
+        }
+        None => aliases_data.add_alias_bytes(alias.to_string(), &value, kind, &dek)?,
+    }
+    aliases_data.set_tags(alias, &args.tags);
+    aliases_data.set_interpolate(alias, args.interpolate);
     encrypt_and_save_vault(&vault_info.path, &vault_file, &aliases_data, &dek)?;
 
     let scope = match vault_info.vault_type {
@@ -79,20 +144,22 @@ fn add_from_global(alias: &str) -> ObscuraResult<()> {
         load_vault(&project_vault_info.path)?;
 
     let value = global_aliases
-        .get_alias(alias, &global_dek)?
+        .get_alias_bytes(alias, &global_dek)?
         .ok_or_else(|| ObscuraError::AliasNotFound(alias.to_string()))?;
+    let kind = global_aliases.alias_kind(alias).unwrap_or_default();
 
-    if project_aliases.aliases.contains_key(alias) {
-        if !prompt_yes_no(&format!(
+    if project_aliases.aliases.contains_key(alias)
+        && !prompt_yes_no(&format!(
             "Alias '{}' already exists in project vault. Overwrite?",
             alias
-        ))? {
-            println!("Cancelled");
-            return Ok(());
-        }
+        ))?
+    {
+        println!("Cancelled");
+        return Ok(());
     }
 
-    project_aliases.add_alias(alias.to_string(), value, &project_dek)?;
+    project_aliases.add_alias_bytes(alias.to_string(), &value, kind, &project_dek)?;
+    project_aliases.set_interpolate(alias, global_aliases.interpolates(alias));
     encrypt_and_save_vault(
         &project_vault_info.path,
         &project_vault_file,
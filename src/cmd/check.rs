@@ -0,0 +1,208 @@
+use crate::cmd::common::SecretResolver;
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::time::{age_seconds, format_age};
+use crate::vault::manager::VaultType;
+use crate::vault::manifest::Manifest;
+use clap::Args;
+use serde_json::{json, to_string_pretty};
+
+#[derive(Args)]
+pub struct CheckArgs {
+    #[arg(long, short = 'g', help = "Check against the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Check against the project vault")]
+    pub project: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["global", "project"],
+        help = "Check against project vaults merged over the global vault"
+    )]
+    pub layered: bool,
+
+    #[arg(long, help = "Render output as JSON")]
+    pub json: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Missing,
+    Optional,
+    Expired,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Missing => "missing",
+            Status::Optional => "optional",
+            Status::Expired => "expired",
+        }
+    }
+}
+
+struct Report {
+    alias: String,
+    env: String,
+    status: Status,
+    vault: Option<String>,
+    description: Option<String>,
+    age_seconds: Option<i64>,
+    max_age_days: Option<u64>,
+}
+
+// Compares the project's obscura.toml with the vaults: declared secrets
+// that are missing or older than `max_age_days`, and aliases nobody declared.
+pub fn handle_check(args: CheckArgs) -> ObscuraResult<()> {
+    let manifest = Manifest::find_current()?.ok_or_else(|| {
+        ObscuraError::CustomError(
+            "No obscura.toml found in this directory or its parents".to_string(),
+        )
+    })?;
+    let mut resolver = SecretResolver::open(args.layered, args.global, args.project)?;
+
+    let mut reports = Vec::new();
+    for (alias, spec) in &manifest.secrets {
+        let index = resolver.locate_in(spec.vault, alias)?;
+        let (vault, age) = match index {
+            Some(index) => {
+                let data = &resolver.layer(index)?.aliases.aliases[alias];
+                let age = age_seconds(data.rotated_at.as_deref().unwrap_or(&data.created_at));
+                (Some(resolver.layer_label(index)), age)
+            }
+            None => (None, None),
+        };
+        let status = match (index, spec.max_age_seconds(), age) {
+            (None, _, _) if spec.required => Status::Missing,
+            (None, _, _) => Status::Optional,
+            (Some(_), Some(max_age), Some(age)) if age > max_age => Status::Expired,
+            (Some(_), _, _) => Status::Ok,
+        };
+        reports.push(Report {
+            alias: alias.clone(),
+            env: spec.env_name(alias),
+            status,
+            vault,
+            description: spec.description.clone(),
+            age_seconds: age,
+            max_age_days: spec.max_age_days,
+        });
+    }
+
+    // Shared global secrets are not expected to be declared by every project.
+    let has_project_layer =
+        (0..resolver.layer_count()).any(|index| resolver.layer_type(index) == VaultType::Project);
+    let mut extra = Vec::new();
+    for (alias, index) in resolver.entries()? {
+        if manifest.secrets.contains_key(&alias)
+            || (has_project_layer && resolver.layer_type(index) == VaultType::Global)
+        {
+            continue;
+        }
+        extra.push((alias, resolver.layer_label(index)));
+    }
+
+    let failed = reports
+        .iter()
+        .any(|report| matches!(report.status, Status::Missing | Status::Expired));
+
+    if args.json {
+        let secrets: Vec<_> = reports
+            .iter()
+            .map(|report| {
+                json!({
+                    "alias": report.alias,
+                    "env": report.env,
+                    "status": report.status.label(),
+                    "vault": report.vault,
+                    "description": report.description,
+                    "age_seconds": report.age_seconds,
+                    "max_age_days": report.max_age_days,
+                })
+            })
+            .collect();
+        let extra: Vec<_> = extra
+            .iter()
+            .map(|(alias, vault)| json!({ "alias": alias, "vault": vault }))
+            .collect();
+        println!(
+            "{}",
+            to_string_pretty(&json!({
+                "manifest": manifest.path,
+                "ok": !failed,
+                "secrets": secrets,
+                "extra": extra,
+            }))?
+        );
+    } else {
+        print_report(&manifest, &reports, &extra);
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn print_report(manifest: &Manifest, reports: &[Report], extra: &[(String, String)]) {
+    println!("Checking {}", manifest.path.display());
+    let width = reports
+        .iter()
+        .map(|report| report.alias.len())
+        .chain(extra.iter().map(|(alias, _)| alias.len()))
+        .max()
+        .unwrap_or(0);
+
+    for report in reports {
+        let detail = match report.status {
+            Status::Missing => "required, not set".to_string(),
+            Status::Optional => "optional, not set".to_string(),
+            Status::Expired => format!(
+                "changed {} ago, max {}d",
+                report.age_seconds.map(format_age).unwrap_or_default(),
+                report.max_age_days.unwrap_or_default()
+            ),
+            Status::Ok => report.vault.clone().unwrap_or_default(),
+        };
+        let description = report
+            .description
+            .as_deref()
+            .map(|description| format!("  # {}", description))
+            .unwrap_or_default();
+        println!(
+            "  {:<8}  {:<width$}  {}{}",
+            report.status.label(),
+            report.alias,
+            detail,
+            description,
+            width = width
+        );
+    }
+    for (alias, vault) in extra {
+        println!(
+            "  {:<8}  {:<width$}  {}, not in manifest",
+            "extra",
+            alias,
+            vault,
+            width = width
+        );
+    }
+
+    let count = |status: Status| {
+        reports
+            .iter()
+            .filter(|report| report.status == status)
+            .count()
+    };
+    println!(
+        "{} ok, {} missing, {} expired, {} optional unset, {} extra",
+        count(Status::Ok),
+        count(Status::Missing),
+        count(Status::Expired),
+        count(Status::Optional),
+        extra.len()
+    );
+}
@@ -1,25 +1,49 @@
+use crate::cmd::otp::next_code;
 use crate::crypto::aead::AeadKey;
 use crate::session::SessionStore;
-use crate::util::errors::ObscuraResult;
+use crate::util::errors::{ObscuraError, ObscuraResult};
 use crate::util::io::{get_passphrase_from_env, prompt_passphrase};
-use crate::vault::file::{decrypt_vault, decrypt_vault_with_dek, read_vault_file};
-use crate::vault::model::{AliasesData, VaultFile};
-use std::path::Path;
+use crate::vault::file::{
+    decrypt_vault, decrypt_vault_with_dek, encrypt_and_save_vault, read_vault_file, vault_exists,
+};
+use crate::vault::interpolate::{has_references, parse_template, Segment};
+use crate::vault::manager::{VaultInfo, VaultManager, VaultType};
+use crate::vault::model::{AliasKind, AliasesData, VaultFile};
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
 pub fn load_vault(vault_path: &Path) -> ObscuraResult<(AeadKey, AliasesData, VaultFile)> {
+    open_vault(vault_path, true)
+}
+
+// Like `load_vault`, but fails instead of asking for the passphrase when the
+// vault is not unlocked and OBSCURA_PASSPHRASE is not set.
+fn open_vault(
+    vault_path: &Path,
+    may_prompt: bool,
+) -> ObscuraResult<(AeadKey, AliasesData, VaultFile)> {
     let vault_file = read_vault_file(vault_path)?;
 
     if let Some(dek) = SessionStore::fetch_dek(vault_path)? {
         let aliases_data = decrypt_vault_with_dek(&vault_file, &dek)?;
+        VaultManager::touch_project(vault_path);
         return Ok((dek, aliases_data, vault_file));
     }
 
     let passphrase = match get_passphrase_from_env() {
         Some(value) => value,
-        None => prompt_passphrase()?,
+        None if may_prompt => prompt_passphrase()?,
+        None => {
+            return Err(ObscuraError::CustomError(format!(
+                "{} is locked (run 'obscura unlock')",
+                vault_path.display()
+            )))
+        }
     };
 
     let (dek, aliases_data) = decrypt_vault(&vault_file, &passphrase)?;
+    VaultManager::touch_project(vault_path);
 
     Ok((dek, aliases_data, vault_file))
 }
@@ -28,3 +52,392 @@ pub fn load_aliases(vault_path: &Path) -> ObscuraResult<(AeadKey, AliasesData)>
     let (dek, aliases_data, _) = load_vault(vault_path)?;
     Ok((dek, aliases_data))
 }
+
+pub fn vault_scope(vault_type: VaultType) -> &'static str {
+    match vault_type {
+        VaultType::Global => "global",
+        VaultType::Project => "project",
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum VaultScope {
+    Global,
+    Project,
+}
+
+impl VaultScope {
+    pub fn resolve(self) -> ObscuraResult<VaultInfo> {
+        match self {
+            VaultScope::Global => VaultManager::resolve_vault(true, false),
+            VaultScope::Project => VaultManager::resolve_vault(false, true),
+        }
+    }
+}
+
+pub struct LoadedVault {
+    pub info: VaultInfo,
+    pub dek: AeadKey,
+    pub aliases: AliasesData,
+    pub file: VaultFile,
+}
+
+impl LoadedVault {
+    pub fn load(info: VaultInfo) -> ObscuraResult<Self> {
+        Self::open(info, true)
+    }
+
+    fn open(info: VaultInfo, may_prompt: bool) -> ObscuraResult<Self> {
+        if !vault_exists(&info.path) {
+            return Err(ObscuraError::VaultNotFound);
+        }
+        let (dek, aliases, file) = open_vault(&info.path, may_prompt)?;
+        Ok(Self {
+            info,
+            dek,
+            aliases,
+            file,
+        })
+    }
+
+    pub fn save(&self) -> ObscuraResult<()> {
+        encrypt_and_save_vault(&self.info.path, &self.file, &self.aliases, &self.dek)
+    }
+}
+
+// Looks aliases up and expands `${...}` references in values added with
+// `--interpolate`. In layered mode lookups
+// fall through the project vaults to the global vault; vaults are unlocked
+// only when a lookup actually reaches them.
+pub struct SecretResolver {
+    layers: Vec<Layer>,
+    layered: bool,
+    resolved: HashMap<(usize, String), String>,
+    // Values as commands receive them, kept until the next reload so an OTP
+    // code is generated once per launch however often its alias is used.
+    bytes: HashMap<(usize, String), Option<Vec<u8>>>,
+    may_prompt: bool,
+}
+
+struct Layer {
+    info: VaultInfo,
+    vault: Option<LoadedVault>,
+}
+
+impl SecretResolver {
+    pub fn new(vault: LoadedVault) -> Self {
+        Self {
+            layers: vec![Layer {
+                info: vault.info.clone(),
+                vault: Some(vault),
+            }],
+            layered: false,
+            resolved: HashMap::new(),
+            bytes: HashMap::new(),
+            may_prompt: true,
+        }
+    }
+
+    pub fn layered(layers: Vec<VaultInfo>) -> ObscuraResult<Self> {
+        if layers.is_empty() {
+            return Err(ObscuraError::VaultNotFound);
+        }
+        Ok(Self {
+            layers: layers
+                .into_iter()
+                .map(|info| Layer { info, vault: None })
+                .collect(),
+            layered: true,
+            resolved: HashMap::new(),
+            bytes: HashMap::new(),
+            may_prompt: true,
+        })
+    }
+
+    pub fn open(layered: bool, global: bool, project: bool) -> ObscuraResult<Self> {
+        if layered {
+            Self::layered(VaultManager::resolve_layers()?)
+        } else {
+            let vault_info = VaultManager::resolve_vault(global, project)?;
+            Ok(Self::new(LoadedVault::load(vault_info)?))
+        }
+    }
+
+    pub fn layer_count(&self) -> usize {
+        if self.layered {
+            self.layers.len()
+        } else {
+            1
+        }
+    }
+
+    pub fn layer(&mut self, index: usize) -> ObscuraResult<&mut LoadedVault> {
+        let layer = &mut self.layers[index];
+        if layer.vault.is_none() {
+            layer.vault = Some(LoadedVault::open(layer.info.clone(), self.may_prompt)?);
+        }
+        Ok(layer.vault.as_mut().expect("layer was just loaded"))
+    }
+
+    // `project` is the nearest project vault; vaults of parent directories are
+    // labelled with their directory.
+    pub fn layer_label(&self, index: usize) -> String {
+        let info = &self.layers[index].info;
+        let nearest_project = self
+            .layers
+            .iter()
+            .position(|layer| layer.info.vault_type == VaultType::Project);
+        match (&info.project_dir, nearest_project) {
+            (Some(dir), Some(nearest)) if nearest != index => {
+                format!("project:{}", dir.display())
+            }
+            _ => vault_scope(info.vault_type).to_string(),
+        }
+    }
+
+    pub fn locate(&mut self, alias: &str) -> ObscuraResult<Option<usize>> {
+        self.locate_from(0, alias)
+    }
+
+    // Like `locate`, but restricted to the nearest vault of `vault_type` when given.
+    pub fn locate_in(
+        &mut self,
+        vault_type: Option<VaultType>,
+        alias: &str,
+    ) -> ObscuraResult<Option<usize>> {
+        let index = match vault_type {
+            None => return self.locate(alias),
+            Some(vault_type) => match self.scoped_layer(vault_type)? {
+                Some(index) => index,
+                None => return Ok(None),
+            },
+        };
+        if self.layer(index)?.aliases.aliases.contains_key(alias) {
+            Ok(Some(index))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub fn layer_type(&self, index: usize) -> VaultType {
+        self.layers[index].info.vault_type
+    }
+
+    pub fn vault_paths(&self) -> Vec<PathBuf> {
+        self.layers
+            .iter()
+            .map(|layer| layer.info.path.clone())
+            .collect()
+    }
+
+    // Vaults unlocked from now on use the session or OBSCURA_PASSPHRASE only,
+    // for callers that run unattended.
+    pub fn never_prompt(&mut self) {
+        self.may_prompt = false;
+    }
+
+    // Re-reads the vaults after they changed on disk. Loaded vaults are
+    // decrypted with the key already in memory; only a vault whose key
+    // changed is unlocked again.
+    pub fn reload(&mut self) -> ObscuraResult<()> {
+        for layer in &mut self.layers {
+            if let Some(vault) = &mut layer.vault {
+                let file = read_vault_file(&layer.info.path)?;
+                match decrypt_vault_with_dek(&file, &vault.dek) {
+                    Ok(aliases) => {
+                        vault.aliases = aliases;
+                        vault.file = file;
+                    }
+                    Err(_) => *vault = LoadedVault::open(layer.info.clone(), self.may_prompt)?,
+                }
+            }
+        }
+        self.resolved.clear();
+        self.bytes.clear();
+        Ok(())
+    }
+
+    // Every visible alias, sorted, with the layer it is taken from.
+    pub fn entries(&mut self) -> ObscuraResult<Vec<(String, usize)>> {
+        let mut entries = BTreeMap::new();
+        for index in 0..self.layer_count() {
+            for alias in self.layer(index)?.aliases.list_aliases() {
+                entries.entry(alias).or_insert(index);
+            }
+        }
+        Ok(entries.into_iter().collect())
+    }
+
+    pub fn resolve(&mut self, alias: &str) -> ObscuraResult<Option<String>> {
+        match self.locate(alias)? {
+            Some(index) => {
+                let mut chain = Vec::new();
+                self.resolve_in(index, alias, &mut chain).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    // The value as a command or file receives it: text with references
+    // expanded, file contents, or the current code of an OTP secret.
+    pub fn value_bytes(&mut self, alias: &str) -> ObscuraResult<Option<Vec<u8>>> {
+        match self.locate(alias)? {
+            Some(index) => self.value_bytes_at(index, alias),
+            None => Ok(None),
+        }
+    }
+
+    pub fn value_bytes_at(&mut self, index: usize, alias: &str) -> ObscuraResult<Option<Vec<u8>>> {
+        let key = (index, alias.to_string());
+        if let Some(value) = self.bytes.get(&key) {
+            return Ok(value.clone());
+        }
+        let vault = self.layer(index)?;
+        let value = match vault.aliases.alias_kind(alias) {
+            Some(AliasKind::Otp) => Some(next_code(vault, alias)?.code.into_bytes()),
+            Some(AliasKind::File) => vault.aliases.get_alias_bytes(alias, &vault.dek)?,
+            _ => Some(self.resolve_at(index, alias)?.into_bytes()),
+        };
+        self.bytes.insert(key, value.clone());
+        Ok(value)
+    }
+
+    pub fn resolve_at(&mut self, index: usize, alias: &str) -> ObscuraResult<String> {
+        let mut chain = Vec::new();
+        self.resolve_in(index, alias, &mut chain)
+    }
+
+    fn locate_from(&mut self, start: usize, alias: &str) -> ObscuraResult<Option<usize>> {
+        let end = if self.layered {
+            self.layers.len()
+        } else {
+            start + 1
+        };
+        for index in start..end {
+            if self.layer(index)?.aliases.aliases.contains_key(alias) {
+                return Ok(Some(index));
+            }
+        }
+        Ok(None)
+    }
+
+    fn resolve_in(
+        &mut self,
+        index: usize,
+        alias: &str,
+        chain: &mut Vec<(usize, String)>,
+    ) -> ObscuraResult<String> {
+        let key = (index, alias.to_string());
+        if let Some(value) = self.resolved.get(&key) {
+            return Ok(value.clone());
+        }
+        if chain.contains(&key) {
+            chain.push(key);
+            return Err(ObscuraError::ReferenceCycle(self.describe_chain(chain)));
+        }
+
+        let (raw, interpolate) = {
+            let vault = self.layer(index)?;
+            let raw = vault
+                .aliases
+                .get_alias(alias, &vault.dek)?
+                .ok_or_else(|| ObscuraError::AliasNotFound(alias.to_string()))?;
+            (raw, vault.aliases.interpolates(alias))
+        };
+
+        if !interpolate || !has_references(&raw) {
+            self.resolved.insert(key, raw.clone());
+            return Ok(raw);
+        }
+
+        chain.push(key.clone());
+        let mut value = String::new();
+        for segment in parse_template(&raw)? {
+            match segment {
+                Segment::Literal(text) => value.push_str(&text),
+                Segment::Reference(reference) => {
+                    // Unqualified references look in the referencing vault and,
+                    // when layered, in the layers below it.
+                    let target = match reference.scope {
+                        None => self.locate_from(index, &reference.alias)?,
+                        Some(vault_type) => match self.scoped_layer(vault_type)? {
+                            Some(target)
+                                if self
+                                    .layer(target)?
+                                    .aliases
+                                    .aliases
+                                    .contains_key(&reference.alias) =>
+                            {
+                                Some(target)
+                            }
+                            _ => None,
+                        },
+                    };
+                    let target = target.ok_or_else(|| {
+                        ObscuraError::UnresolvedReference(alias.to_string(), reference.to_string())
+                    })?;
+                    // Seeds and file contents are never spliced into a text
+                    // value; an OTP reference gets the current code instead.
+                    match self.layer(target)?.aliases.alias_kind(&reference.alias) {
+                        Some(AliasKind::Otp) => {
+                            let code = self
+                                .value_bytes_at(target, &reference.alias)?
+                                .unwrap_or_default();
+                            value.push_str(&String::from_utf8_lossy(&code));
+                        }
+                        Some(AliasKind::File) => {
+                            return Err(ObscuraError::CustomError(format!(
+                                "'{}' references '{}', which is a file secret; file secrets cannot be referenced",
+                                alias, reference
+                            )))
+                        }
+                        _ => value.push_str(&self.resolve_in(target, &reference.alias, chain)?),
+                    }
+                }
+            }
+        }
+        chain.pop();
+
+        self.resolved.insert(key, value.clone());
+        Ok(value)
+    }
+
+    // The layer holding the nearest vault of the given type, adding it on
+    // demand when it is not one of the layers yet.
+    fn scoped_layer(&mut self, vault_type: VaultType) -> ObscuraResult<Option<usize>> {
+        if let Some(index) = self
+            .layers
+            .iter()
+            .position(|layer| layer.info.vault_type == vault_type)
+        {
+            return Ok(Some(index));
+        }
+        if self.layered {
+            return Ok(None);
+        }
+
+        let info = match vault_type {
+            VaultType::Global => VaultManager::resolve_vault(true, false)?,
+            VaultType::Project => VaultManager::resolve_vault(false, true)?,
+        };
+        if !vault_exists(&info.path) {
+            return Ok(None);
+        }
+        self.layers.push(Layer { info, vault: None });
+        Ok(Some(self.layers.len() - 1))
+    }
+
+    fn describe_chain(&self, chain: &[(usize, String)]) -> String {
+        chain
+            .iter()
+            .map(|(index, alias)| {
+                if *index == 0 {
+                    alias.clone()
+                } else {
+                    format!("{}:{}", self.layer_label(*index), alias)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" -> ")
+    }
+}
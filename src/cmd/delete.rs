@@ -25,14 +25,26 @@ pub fn handle_delete(args: DeleteArgs) -> ObscuraResult<()> {
     }
 
     if !args.yes {
-        let message = match vault_info.vault_type {
-            VaultType::Global => "Delete the global vault? This cannot be undone.",
-            VaultType::Project => {
-                "Delete the project vault for this directory? This cannot be undone."
+        let message = match (vault_info.vault_type, &vault_info.project_dir) {
+            (VaultType::Global, _) => "Delete the global vault? This cannot be undone.".to_string(),
+            (VaultType::Project, project_dir) if vault_info.environment.is_some() => format!(
+                "Delete the '{}' environment of the project vault for {}? The default vault and other environments are kept. This cannot be undone.",
+                vault_info.environment.as_deref().unwrap_or_default(),
+                project_dir
+                    .as_ref()
+                    .map(|dir| dir.display().to_string())
+                    .unwrap_or_else(|| "this directory".to_string())
+            ),
+            (VaultType::Project, Some(project_dir)) => format!(
+                "Delete the project vault for {}? This cannot be undone.",
+                project_dir.display()
+            ),
+            (VaultType::Project, None) => {
+                "Delete the project vault for this directory? This cannot be undone.".to_string()
             }
         };
 
-        if !prompt_yes_no(message)? {
+        if !prompt_yes_no(&message)? {
             println!("Cancelled");
             return Ok(());
         }
@@ -46,6 +58,9 @@ pub fn handle_delete(args: DeleteArgs) -> ObscuraResult<()> {
         VaultType::Project => "project",
     };
 
-    println!("Deleted {} vault", scope);
+    match &vault_info.environment {
+        Some(environment) => println!("Deleted {} vault (environment '{}')", scope, environment),
+        None => println!("Deleted {} vault", scope),
+    }
     Ok(())
 }
@@ -0,0 +1,192 @@
+use crate::cmd::run::{env_names, env_values, RunArgs};
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::io::prompt_yes_no_on_stderr;
+use crate::vault::manager::VaultManager;
+use clap::{Args, ValueEnum};
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Args)]
+pub struct EnvArgs {
+    #[arg(
+        long,
+        value_enum,
+        help = "Shell syntax to print (detected from $SHELL by default)"
+    )]
+    pub shell: Option<Shell>,
+
+    #[arg(long, help = "Print statements that remove the variables again")]
+    pub unset: bool,
+
+    #[arg(long, short = 'g', help = "Use the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Use the project vault")]
+    pub project: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["global", "project"],
+        help = "Use project vaults merged over the global vault"
+    )]
+    pub layered: bool,
+
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "NAMESPACE",
+        help = "Only aliases under this namespace, named by their leaf names"
+    )]
+    pub namespace: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_delimiter = ',',
+        help = "Only matching aliases (comma-separated names or globs)"
+    )]
+    pub only: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_delimiter = ',',
+        help = "Leave out matching aliases (comma-separated names or globs)"
+    )]
+    pub exclude: Vec<String>,
+
+    #[arg(long, help = "Ignore obscura.toml and use every alias")]
+    pub no_manifest: bool,
+
+    #[arg(long, help = "Skip the confirmation for protected environments")]
+    pub yes: bool,
+}
+
+// Prints the variables `run` would set as statements for the shell to
+// evaluate, e.g. `eval "$(obscura env)"`.
+pub fn handle_env(args: EnvArgs) -> ObscuraResult<()> {
+    let shell = match args.shell {
+        Some(shell) => shell,
+        None => detect_shell()?,
+    };
+    let run_args = RunArgs {
+        global: args.global,
+        project: args.project,
+        layered: args.layered,
+        namespace: args.namespace,
+        only: args.only,
+        exclude: args.exclude,
+        no_manifest: args.no_manifest,
+        ..Default::default()
+    };
+
+    if args.unset {
+        for name in env_names(&run_args)? {
+            if is_variable_name(&name) {
+                println!("{}", unset_statement(shell, &name));
+            }
+        }
+        return Ok(());
+    }
+
+    // The statements go to stdout for the shell to evaluate, so the question
+    // goes to stderr.
+    if !args.global && !args.yes {
+        let vault_info = VaultManager::resolve_vault(false, args.project)?;
+        if VaultManager::is_protected(&vault_info) {
+            let message = format!(
+                "Print secrets from the protected '{}' environment?",
+                vault_info.environment.as_deref().unwrap_or_default()
+            );
+            if !prompt_yes_no_on_stderr(&message)? {
+                eprintln!("Cancelled");
+                return Ok(());
+            }
+        }
+    }
+
+    for (name, value) in env_values(&run_args)? {
+        if !is_variable_name(&name) {
+            eprintln!("Skipping '{}' - not a valid variable name", name);
+            continue;
+        }
+        println!("{}", set_statement(shell, &name, &value));
+    }
+    Ok(())
+}
+
+fn detect_shell() -> ObscuraResult<Shell> {
+    let from_env = std::env::var_os("SHELL").and_then(|shell| {
+        let name = Path::new(&shell)
+            .file_stem()?
+            .to_string_lossy()
+            .to_lowercase();
+        match name.as_str() {
+            "bash" | "sh" => Some(Shell::Bash),
+            "zsh" => Some(Shell::Zsh),
+            "fish" => Some(Shell::Fish),
+            "pwsh" | "powershell" => Some(Shell::Powershell),
+            _ => None,
+        }
+    });
+    match from_env {
+        Some(shell) => Ok(shell),
+        None if cfg!(windows) => Ok(Shell::Powershell),
+        None => Err(ObscuraError::CustomError(
+            "Cannot tell the shell from $SHELL; pass --shell".to_string(),
+        )),
+    }
+}
+
+fn is_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn set_statement(shell: Shell, name: &str, value: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("export {}={}", name, quote_posix(value)),
+        Shell::Fish => format!("set -gx {} {}", name, quote_fish(value)),
+        Shell::Powershell => format!("$env:{} = {}", name, quote_powershell(value)),
+    }
+}
+
+fn unset_statement(shell: Shell, name: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("unset {}", name),
+        Shell::Fish => format!("set -e {}", name),
+        Shell::Powershell => format!("Remove-Item Env:{} -ErrorAction SilentlyContinue", name),
+    }
+}
+
+// Nothing is special inside single quotes, so only the quote itself needs
+// closing, escaping and reopening.
+fn quote_posix(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn quote_fish(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+// PowerShell also ends single-quoted strings at typographic single quotes;
+// each quote character is escaped by doubling it.
+fn quote_powershell(value: &str) -> String {
+    let mut quoted = String::from("'");
+    for c in value.chars() {
+        if matches!(c, '\'' | '\u{2018}' | '\u{2019}' | '\u{201A}' | '\u{201B}') {
+            quoted.push(c);
+        }
+        quoted.push(c);
+    }
+    quoted.push('\'');
+    quoted
+}
@@ -1,7 +1,7 @@
-use crate::cmd::common::load_aliases;
+use crate::cmd::common::SecretResolver;
 use crate::util::errors::{ObscuraError, ObscuraResult};
-use crate::vault::file::vault_exists;
-use crate::vault::manager::VaultManager;
+use crate::vault::model::AliasKind;
+use crate::vault::namespace::is_namespaced;
 use clap::Args;
 use std::fs;
 use std::path::Path;
@@ -14,6 +14,9 @@ pub struct ExportDotenvArgs {
     #[arg(long, short = 'p', help = "Export from the project vault")]
     pub project: bool,
 
+    #[arg(long, help = "Merge project vaults over the global vault")]
+    pub layered: bool,
+
     #[arg(long, help = "Write output to this file path")]
     pub output: Option<String>,
 
@@ -22,22 +25,49 @@ pub struct ExportDotenvArgs {
 }
 
 pub fn handle_export_dotenv(args: ExportDotenvArgs) -> ObscuraResult<()> {
-    let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
-
-    if !vault_exists(&vault_info.path) {
-        return Err(ObscuraError::VaultNotFound);
-    }
-
-    let (dek, aliases_data) = load_aliases(&vault_info.path)?;
-    let aliases = aliases_data.list_aliases();
+    let mut resolver = SecretResolver::open(args.layered, args.global, args.project)?;
+    let entries = resolver.entries()?;
 
     let mut dotenv_content = String::new();
-    for alias in &aliases {
-        if let Some(value) = aliases_data.get_alias(alias, &dek)? {
+    let mut exported = 0;
+    let mut nested = Vec::new();
+    for (alias, index) in &entries {
+        // `a/b` is not a valid dotenv key.
+        if is_namespaced(alias) {
+            nested.push(alias.as_str());
+            continue;
+        }
+        match resolver.layer(*index)?.aliases.alias_kind(alias) {
+            Some(AliasKind::File) => {
+                eprintln!(
+                    "Skipping '{}' - file secrets cannot be exported as dotenv",
+                    alias
+                );
+                continue;
+            }
+            Some(AliasKind::Otp) => {
+                eprintln!(
+                    "Skipping '{}' - OTP seeds are not exported; use 'obscura otp {}' for codes",
+                    alias, alias
+                );
+                continue;
+            }
+            _ => {}
+        }
+        if let Some(value) = resolver.resolve(alias)? {
             dotenv_content.push_str(&format!("{}={}\n", alias, value));
+            exported += 1;
         }
     }
 
+    if !nested.is_empty() {
+        eprintln!(
+            "Skipping {} namespaced alias(es), which are not valid dotenv keys: {}",
+            nested.len(),
+            nested.join(", ")
+        );
+    }
+
     if let Some(output_path) = args.output {
         let path = Path::new(&output_path);
 
@@ -54,7 +84,7 @@ pub fn handle_export_dotenv(args: ExportDotenvArgs) -> ObscuraResult<()> {
             fs::set_permissions(path, perms)?;
         }
 
-        println!("Exported {} aliases to {}", aliases.len(), output_path);
+        println!("Exported {} aliases to {}", exported, output_path);
     } else {
         print!("{}", dotenv_content);
     }
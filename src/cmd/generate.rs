@@ -0,0 +1,27 @@
+use crate::crypto::generate::{generate_secret, DEFAULT_SPEC};
+use crate::util::errors::ObscuraResult;
+use clap::Args;
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    #[arg(
+        default_value = DEFAULT_SPEC,
+        help = "Generator spec: MODE[:LENGTH][,OPTION...] with modes chars, hex, base64, base32, uuid, words"
+    )]
+    pub spec: String,
+
+    #[arg(
+        long,
+        short = 'n',
+        default_value_t = 1,
+        help = "Number of values to generate"
+    )]
+    pub count: usize,
+}
+
+pub fn handle_generate(args: GenerateArgs) -> ObscuraResult<()> {
+    for _ in 0..args.count {
+        println!("{}", generate_secret(&args.spec)?);
+    }
+    Ok(())
+}
@@ -1,8 +1,10 @@
-use crate::cmd::common::load_aliases;
+use crate::cmd::common::SecretResolver;
 use crate::util::errors::{ObscuraError, ObscuraResult};
-use crate::vault::file::vault_exists;
-use crate::vault::manager::VaultManager;
+use crate::util::io::write_private_file;
+use crate::vault::model::AliasKind;
 use clap::Args;
+use std::io::{self, IsTerminal, Write};
+use std::path::Path;
 
 #[derive(Args)]
 pub struct GetArgs {
@@ -14,22 +16,84 @@ pub struct GetArgs {
 
     #[arg(long, short = 'p', help = "Read from the project vault")]
     pub project: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["global", "project"],
+        help = "Fall through project vaults to the global vault"
+    )]
+    pub layered: bool,
+
+    #[arg(
+        long,
+        short = 'o',
+        value_name = "PATH",
+        help = "Write the value to this file (created with 0600 permissions)"
+    )]
+    pub output: Option<String>,
+
+    #[arg(long, requires = "output", help = "Allow overwriting the output file")]
+    pub overwrite: bool,
+
+    #[arg(
+        long,
+        help = "Print the stored value without resolving ${...} references"
+    )]
+    pub raw: bool,
+
+    #[arg(
+        long,
+        help = "Print the seed of an OTP alias instead of refusing (use 'obscura otp' for codes)"
+    )]
+    pub reveal_seed: bool,
 }
 
 pub fn handle_get(args: GetArgs) -> ObscuraResult<()> {
-    let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
+    let mut resolver = SecretResolver::open(args.layered, args.global, args.project)?;
+    let index = resolver
+        .locate(&args.alias)?
+        .ok_or_else(|| ObscuraError::AliasNotFound(args.alias.clone()))?;
+
+    let vault = resolver.layer(index)?;
+    let kind = vault
+        .aliases
+        .alias_kind(&args.alias)
+        .ok_or_else(|| ObscuraError::AliasNotFound(args.alias.clone()))?;
 
-    if !vault_exists(&vault_info.path) {
-        return Err(ObscuraError::VaultNotFound);
+    if kind == AliasKind::Otp && !args.reveal_seed {
+        return Err(ObscuraError::CustomError(format!(
+            "'{}' is an OTP seed. Use 'obscura otp {}' for the current code, or --reveal-seed to print the seed",
+            args.alias, args.alias
+        )));
     }
 
-    let (dek, aliases_data) = load_aliases(&vault_info.path)?;
+    let value = if kind != AliasKind::Text || args.raw {
+        vault
+            .aliases
+            .get_alias_bytes(&args.alias, &vault.dek)?
+            .ok_or_else(|| ObscuraError::AliasNotFound(args.alias.clone()))?
+    } else {
+        resolver
+            .resolve(&args.alias)?
+            .ok_or_else(|| ObscuraError::AliasNotFound(args.alias.clone()))?
+            .into_bytes()
+    };
 
-    match aliases_data.get_alias(&args.alias, &dek)? {
-        Some(value) => {
-            print!("{}", value);
-            Ok(())
+    if let Some(output_path) = args.output {
+        let path = Path::new(&output_path);
+        if path.exists() && !args.overwrite {
+            return Err(ObscuraError::FileExists(output_path));
         }
-        None => Err(ObscuraError::AliasNotFound(args.alias)),
+        write_private_file(path, &value)?;
+        eprintln!("Wrote '{}' to {}", args.alias, output_path);
+        return Ok(());
+    }
+
+    let mut stdout = io::stdout();
+    if kind == AliasKind::File && stdout.is_terminal() {
+        return Err(ObscuraError::BinaryValue(args.alias));
     }
+    stdout.write_all(&value)?;
+    stdout.flush()?;
+    Ok(())
 }
@@ -0,0 +1,373 @@
+use crate::cmd::list::kind_label;
+use crate::crypto::aead::AeadKey;
+use crate::session::SessionStore;
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::io::{get_passphrase_from_env, prompt_passphrase};
+use crate::vault::file::{
+    decrypt_vault, decrypt_vault_with_dek, encrypt_and_save_vault, read_vault_file,
+};
+use crate::vault::identity::{repo_envs_dir, repo_vault_path};
+use crate::vault::model::{AliasData, AliasesData, VaultFile, VaultStorage};
+use clap::Args;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CONFLICT_TAG: &str = "merge-conflict";
+const THEIRS_SUFFIX: &str = ".theirs";
+
+#[derive(Args)]
+pub struct GitMergeDriverArgs {
+    #[arg(help = "Common ancestor version (%O)")]
+    pub base: PathBuf,
+
+    #[arg(help = "Current branch version, replaced by the merge result (%A)")]
+    pub ours: PathBuf,
+
+    #[arg(help = "Other branch version (%B)")]
+    pub theirs: PathBuf,
+
+    #[arg(help = "Path of the vault in the work tree (%P), used to find a cached key")]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct GitTextconvArgs {
+    #[arg(help = "Vault file to render")]
+    pub file: PathBuf,
+}
+
+// Git merges vault files through this driver so that aliases added or
+// changed on different branches merge like lines of a text file.
+pub fn handle_git_merge_driver(args: GitMergeDriverArgs) -> ObscuraResult<()> {
+    let mut keys = Keyring::new(args.path.as_deref())?;
+
+    let ours_file = read_vault_file(&args.ours)?;
+    let (ours_dek, ours) = keys.open(&ours_file)?;
+    let (theirs_dek, theirs) = keys.open(&read_vault_file(&args.theirs)?)?;
+    // Git passes an empty base when both branches added the file.
+    let base = if fs::metadata(&args.base).map(|meta| meta.len()).unwrap_or(0) == 0 {
+        None
+    } else {
+        Some(keys.open(&read_vault_file(&args.base)?)?)
+    };
+
+    let mut merged = AliasesData::new();
+    let mut conflicts = Vec::new();
+    let names: BTreeSet<&String> = ours
+        .aliases
+        .keys()
+        .chain(theirs.aliases.keys())
+        .chain(base.iter().flat_map(|(_, base)| base.aliases.keys()))
+        .collect();
+
+    for alias in names {
+        let ours_entry = entry(&ours, &ours_dek, alias)?;
+        let theirs_entry = entry(&theirs, &theirs_dek, alias)?;
+        let base_entry = match &base {
+            Some((base_dek, base)) => entry(base, base_dek, alias)?,
+            None => None,
+        };
+
+        let take_theirs = if let Some(theirs_ahead) = hotp_ahead(&ours_entry, &theirs_entry) {
+            // Codes were generated from the same seed on both branches. The
+            // higher counter wins, so no code that was already used becomes
+            // valid again.
+            theirs_ahead
+        } else if ours_entry == theirs_entry || theirs_entry == base_entry {
+            false
+        } else if ours_entry == base_entry {
+            true
+        } else {
+            conflicts.push(alias.clone());
+            // A change beats a deletion; otherwise ours wins and theirs is kept aside.
+            ours_entry.is_none()
+        };
+
+        let chosen = if take_theirs {
+            theirs.aliases.get(alias).map(|data| (data, &theirs_dek))
+        } else {
+            ours.aliases.get(alias).map(|data| (data, &ours_dek))
+        };
+        if let Some((data, dek)) = chosen {
+            merged
+                .aliases
+                .insert(alias.clone(), rekey(data, dek, &ours_dek)?);
+        }
+    }
+
+    let mut kept_as = BTreeMap::new();
+    for alias in &conflicts {
+        if let Some(data) = merged.aliases.get_mut(alias) {
+            tag_conflict(data);
+        }
+        if let (Some(_), Some(data)) = (ours.aliases.get(alias), theirs.aliases.get(alias)) {
+            let mut data = rekey(data, &theirs_dek, &ours_dek)?;
+            tag_conflict(&mut data);
+            let name = free_name(&merged, &format!("{}{}", alias, THEIRS_SUFFIX));
+            merged.aliases.insert(name.clone(), data);
+            kept_as.insert(alias, name);
+        }
+    }
+
+    encrypt_and_save_vault(&args.ours, &ours_file, &merged, &ours_dek)?;
+
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+    for alias in &conflicts {
+        match (
+            ours.aliases.contains_key(alias),
+            theirs.aliases.contains_key(alias),
+        ) {
+            (true, true) => eprintln!(
+                "CONFLICT (obscura): '{}' changed on both branches; theirs kept as '{}'",
+                alias,
+                kept_as.get(alias).map_or("", String::as_str)
+            ),
+            (true, false) => eprintln!(
+                "CONFLICT (obscura): '{}' changed here and removed on the other branch",
+                alias
+            ),
+            _ => eprintln!(
+                "CONFLICT (obscura): '{}' removed here and changed on the other branch",
+                alias
+            ),
+        }
+    }
+    eprintln!(
+        "Resolve the aliases tagged '{}' with 'obscura mv', 'obscura remove' and 'obscura tag --remove', then commit",
+        CONFLICT_TAG
+    );
+    std::process::exit(1);
+}
+
+// Renders alias names and metadata, never values, so `git diff` on a vault
+// shows which aliases changed. Names and metadata are only readable with a
+// cached key or OBSCURA_PASSPHRASE; otherwise entries are shown by id.
+pub fn handle_git_textconv(args: GitTextconvArgs) -> ObscuraResult<()> {
+    let vault_file = read_vault_file(&args.file)?;
+    println!("obscura vault v{}", vault_file.version);
+    println!("created  {}", vault_file.created_at);
+
+    let aliases_data = match &vault_file.storage {
+        VaultStorage::LegacyEntries { aliases } => AliasesData {
+            aliases: aliases
+                .iter()
+                .map(|(alias, entry)| (alias.clone(), entry.data.clone()))
+                .collect(),
+        },
+        VaultStorage::Entries {
+            revision, entries, ..
+        } => {
+            println!("revision {}", revision);
+            match textconv_key(&args.file, &vault_file) {
+                Some((_, aliases_data)) => aliases_data,
+                None => {
+                    println!("(alias names are encrypted; run 'obscura unlock' to show them)");
+                    for (id, sealed) in entries {
+                        let fingerprint = blake3::hash(sealed.ciphertext_b64.as_bytes()).to_hex();
+                        println!();
+                        println!("[entry {}]", &id[..id.len().min(12)]);
+                        println!("  value    {}", &fingerprint[..12]);
+                    }
+                    return Ok(());
+                }
+            }
+        }
+        VaultStorage::Sealed { .. } => {
+            match textconv_key(&args.file, &vault_file) {
+                Some((_, aliases_data)) => println!(
+                    "sealed vault, {} aliases (use 'obscura init --in-repo' for per-alias diffs)",
+                    aliases_data.aliases.len()
+                ),
+                None => println!(
+                    "sealed vault, alias count unknown while locked (use 'obscura init --in-repo' for per-alias diffs)"
+                ),
+            }
+            return Ok(());
+        }
+    };
+
+    for (alias, data) in &aliases_data.aliases {
+        let fingerprint = blake3::hash(data.value_enc.ciphertext_b64.as_bytes()).to_hex();
+        println!();
+        println!("[{}]", alias);
+        println!("  kind     {}", kind_label(data.kind));
+        println!("  created  {}", data.created_at);
+        println!(
+            "  rotated  {}",
+            data.rotated_at.as_deref().unwrap_or("never")
+        );
+        if !data.tags.is_empty() {
+            println!("  tags     {}", data.tags.join(", "));
+        }
+        println!("  value    {}", &fingerprint[..12]);
+    }
+
+    Ok(())
+}
+
+// Git passes old versions as temporary files, so besides the file itself the
+// keys cached for the in-repo vaults of the work tree (git's working
+// directory) are tried. Never prompts.
+fn textconv_key(file: &Path, vault_file: &VaultFile) -> Option<(AeadKey, AliasesData)> {
+    let mut candidates: Vec<PathBuf> = file.canonicalize().ok().into_iter().collect();
+    if let Ok(current_dir) = std::env::current_dir() {
+        candidates.push(repo_vault_path(&current_dir, None));
+        if let Ok(entries) = fs::read_dir(repo_envs_dir(&current_dir)) {
+            candidates.extend(entries.flatten().map(|entry| entry.path()));
+        }
+    }
+
+    for path in candidates {
+        if let Ok(Some(dek)) = SessionStore::fetch_dek(&path) {
+            if let Ok(aliases_data) = decrypt_vault_with_dek(vault_file, &dek) {
+                return Some((dek, aliases_data));
+            }
+        }
+    }
+    let passphrase = get_passphrase_from_env()?;
+    decrypt_vault(vault_file, &passphrase).ok()
+}
+
+// The three versions usually share one key; each distinct wrapped key is
+// unlocked once, from the session cache when possible.
+struct Keyring {
+    keys: Vec<(String, AeadKey)>,
+    cached: Option<AeadKey>,
+    passphrase: Option<String>,
+}
+
+impl Keyring {
+    fn new(path: Option<&Path>) -> ObscuraResult<Self> {
+        let cached = match path.and_then(|path| path.canonicalize().ok()) {
+            Some(path) => SessionStore::fetch_dek(&path)?,
+            None => None,
+        };
+        Ok(Self {
+            keys: Vec::new(),
+            cached,
+            passphrase: None,
+        })
+    }
+
+    fn open(&mut self, vault_file: &VaultFile) -> ObscuraResult<(AeadKey, AliasesData)> {
+        let wrapped = &vault_file.dek_wrapped.ciphertext_b64;
+        let known = self
+            .keys
+            .iter()
+            .find(|(key_wrapped, _)| key_wrapped == wrapped)
+            .map(|(_, dek)| dek.clone())
+            .or_else(|| self.cached.clone());
+        if let Some(dek) = known {
+            if let Ok(aliases_data) = decrypt_vault_with_dek(vault_file, &dek) {
+                self.remember(wrapped, &dek);
+                return Ok((dek, aliases_data));
+            }
+        }
+
+        let passphrase = match self.passphrase.take() {
+            Some(value) => value,
+            None => match get_passphrase_from_env() {
+                Some(value) => value,
+                None => prompt_passphrase()?,
+            },
+        };
+        let (dek, aliases_data) = decrypt_vault(vault_file, &passphrase)?;
+        self.passphrase = Some(passphrase);
+        self.remember(wrapped, &dek);
+        Ok((dek, aliases_data))
+    }
+
+    fn remember(&mut self, wrapped: &str, dek: &AeadKey) {
+        if !self
+            .keys
+            .iter()
+            .any(|(key_wrapped, _)| key_wrapped == wrapped)
+        {
+            self.keys.push((wrapped.to_string(), dek.clone()));
+        }
+    }
+}
+
+// What a merge compares: the plaintext plus every attribute except
+// timestamps, so the same change made on both branches is not a conflict.
+#[derive(PartialEq)]
+struct Entry {
+    value: Vec<u8>,
+    attributes: serde_json::Value,
+}
+
+fn entry(aliases_data: &AliasesData, dek: &AeadKey, alias: &str) -> ObscuraResult<Option<Entry>> {
+    let data = match aliases_data.aliases.get(alias) {
+        Some(data) => data,
+        None => return Ok(None),
+    };
+    let value = aliases_data
+        .get_alias_bytes(alias, dek)?
+        .ok_or(ObscuraError::DecryptionFailed)?;
+    let mut attributes = serde_json::to_value(data).map_err(|_| ObscuraError::DecryptionFailed)?;
+    if let Some(attributes) = attributes.as_object_mut() {
+        for field in ["value_enc", "created_at", "rotated_at"] {
+            attributes.remove(field);
+        }
+    }
+    Ok(Some(Entry { value, attributes }))
+}
+
+// Whether theirs is ahead, when both sides hold the same HOTP seed and
+// differ at most in the counter.
+fn hotp_ahead(ours: &Option<Entry>, theirs: &Option<Entry>) -> Option<bool> {
+    let (ours, theirs) = (ours.as_ref()?, theirs.as_ref()?);
+    let ours_counter = hotp_counter(ours)?;
+    let theirs_counter = hotp_counter(theirs)?;
+    if ours.value != theirs.value || without_counter(ours) != without_counter(theirs) {
+        return None;
+    }
+    Some(theirs_counter > ours_counter)
+}
+
+fn hotp_counter(entry: &Entry) -> Option<u64> {
+    let otp = entry.attributes.get("otp")?;
+    if otp.get("method")?.as_str()? != "hotp" {
+        return None;
+    }
+    otp.get("counter")?.as_u64()
+}
+
+fn without_counter(entry: &Entry) -> serde_json::Value {
+    let mut attributes = entry.attributes.clone();
+    if let Some(otp) = attributes
+        .get_mut("otp")
+        .and_then(|otp| otp.as_object_mut())
+    {
+        otp.remove("counter");
+    }
+    attributes
+}
+
+// `name`, or `name.2`, `name.3`, ... when an alias already has that name.
+fn free_name(aliases_data: &AliasesData, name: &str) -> String {
+    let mut candidate = name.to_string();
+    let mut suffix = 2;
+    while aliases_data.aliases.contains_key(&candidate) {
+        candidate = format!("{}.{}", name, suffix);
+        suffix += 1;
+    }
+    candidate
+}
+
+fn rekey(data: &AliasData, from: &AeadKey, to: &AeadKey) -> ObscuraResult<AliasData> {
+    if from.as_bytes() == to.as_bytes() {
+        Ok(data.clone())
+    } else {
+        data.reencrypt(from, to)
+    }
+}
+
+fn tag_conflict(data: &mut AliasData) {
+    if !data.tags.iter().any(|tag| tag == CONFLICT_TAG) {
+        data.tags.push(CONFLICT_TAG.to_string());
+    }
+}
@@ -20,7 +20,7 @@ pub struct ImportArgs {
 pub fn handle_import(args: ImportArgs) -> ObscuraResult<()> {
     // Default to project vault unless explicitly specified as global
     let force_global = args.global;
-    let force_project = args.project || (!args.global && !args.project); // Default to project if neither specified
+    let force_project = args.project || !args.global; // Default to project if neither specified
     
     let vault_info = VaultManager::resolve_vault(force_global, force_project)?;
 
@@ -1,20 +1,46 @@
+use crate::cmd::common::load_vault;
 use crate::util::errors::{ObscuraError, ObscuraResult};
 use crate::util::io::{get_passphrase_from_env, prompt_passphrase_confirmation};
-use crate::vault::file::create_vault_file;
-use crate::vault::manager::VaultManager;
+use crate::util::paths::get_project_vault_path;
+use crate::vault::file::{create_repo_vault_file, create_vault_file, encrypt_and_save_vault};
+use crate::vault::identity::ensure_repo_dir;
+use crate::vault::manager::{current_environment, VaultInfo, VaultManager};
+use crate::vault::model::{VaultFile, VaultStorage};
 use clap::Args;
+use std::path::Path;
 
 #[derive(Args)]
 pub struct InitArgs {
     #[arg(long, short = 'g', help = "Initialize the global vault")]
     pub global: bool,
+
+    #[arg(
+        long,
+        help = "Require confirmation before 'run' uses this environment (with --env)"
+    )]
+    pub protected: bool,
+
+    #[arg(
+        long,
+        conflicts_with = "global",
+        help = "Store the vault in .obscura/ inside the project so it can be committed"
+    )]
+    pub in_repo: bool,
 }
 
 pub fn handle_init(args: InitArgs) -> ObscuraResult<()> {
+    if args.protected && (args.global || current_environment().is_none()) {
+        return Err(ObscuraError::CustomError(
+            "--protected applies to project environments; pass --env NAME".to_string(),
+        ));
+    }
+
     if args.global {
         init_global_vault()
+    } else if args.in_repo {
+        init_repo_vault(args.protected)
     } else {
-        init_project_vault()
+        init_project_vault(args.protected)
     }
 }
 
@@ -41,14 +67,24 @@ pub fn init_global_vault() -> ObscuraResult<()> {
     Ok(())
 }
 
-fn init_project_vault() -> ObscuraResult<()> {
+fn init_project_vault(protected: bool) -> ObscuraResult<()> {
     let current_dir = std::env::current_dir().map_err(|_| ObscuraError::FilePermissionError)?;
     VaultManager::ensure_project_vault(&current_dir)?;
 
-    let vault_info = VaultManager::resolve_vault(false, true)?;
+    // Always the current directory, even inside another project.
+    let vault_info = VaultInfo::project(&current_dir)?;
 
     if vault_info.path.exists() {
-        println!("Project vault already exists for this directory");
+        if let Some(environment) = current_environment().filter(|_| protected) {
+            VaultManager::set_protected(&current_dir, environment, true)?;
+        }
+        match current_environment() {
+            Some(environment) => println!(
+                "Environment '{}' already exists for this directory",
+                environment
+            ),
+            None => println!("Project vault already exists for this directory"),
+        }
         return Ok(());
     }
 
@@ -58,10 +94,76 @@ fn init_project_vault() -> ObscuraResult<()> {
     };
 
     create_vault_file(&vault_info.path, &passphrase)?;
+    if let Some(environment) = current_environment().filter(|_| protected) {
+        VaultManager::set_protected(&current_dir, environment, true)?;
+    }
 
-    println!("Project vault created for {}", current_dir.display());
+    match current_environment() {
+        Some(environment) => println!(
+            "Environment '{}'{} created for {}",
+            environment,
+            if protected { " (protected)" } else { "" },
+            current_dir.display()
+        ),
+        None => println!("Project vault created for {}", current_dir.display()),
+    }
     println!("WARNING: This vault is for local development only.");
     println!("WARNING: Losing the passphrase makes the data unrecoverable.");
 
     Ok(())
 }
+
+fn init_repo_vault(protected: bool) -> ObscuraResult<()> {
+    let current_dir = std::env::current_dir().map_err(|_| ObscuraError::FilePermissionError)?;
+    ensure_repo_dir(&current_dir, current_environment())?;
+    let vault_info = VaultInfo::repo(&current_dir);
+
+    if vault_info.path.exists() {
+        println!(
+            "In-repo vault already exists at {}",
+            vault_info.path.display()
+        );
+        return Ok(());
+    }
+
+    // An existing vault in the config directory moves into the repository,
+    // keeping its passphrase and secrets.
+    let local_path = get_project_vault_path(&current_dir, current_environment())?;
+    if local_path.exists() {
+        move_into_repo(&local_path, &vault_info.path)?;
+        println!(
+            "Moved project vault from {} to {}",
+            local_path.display(),
+            vault_info.path.display()
+        );
+    } else {
+        let passphrase = match get_passphrase_from_env() {
+            Some(value) => value,
+            None => prompt_passphrase_confirmation()?,
+        };
+        create_repo_vault_file(&vault_info.path, &passphrase)?;
+        println!("In-repo vault created at {}", vault_info.path.display());
+    }
+    if let Some(environment) = current_environment().filter(|_| protected) {
+        VaultManager::set_protected(&current_dir, environment, true)?;
+        println!(
+            "Environment '{}' is protected for you; protection is not committed.",
+            environment
+        );
+    }
+
+    println!("Commit .obscura/ to share it; share the passphrase through another channel.");
+    println!("WARNING: Losing the passphrase makes the data unrecoverable.");
+
+    Ok(())
+}
+
+fn move_into_repo(local_path: &Path, repo_path: &Path) -> ObscuraResult<()> {
+    let (dek, aliases_data, vault_file) = load_vault(local_path)?;
+    let repo_vault = VaultFile {
+        storage: VaultStorage::empty_entries(),
+        ..vault_file
+    };
+    encrypt_and_save_vault(repo_path, &repo_vault, &aliases_data, &dek)?;
+    VaultManager::retire_project_vault(local_path)
+}
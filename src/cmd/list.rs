@@ -1,12 +1,20 @@
-use crate::cmd::common::load_aliases;
+use crate::cmd::common::{load_aliases, vault_scope, SecretResolver};
+use crate::session::SessionStore;
 use crate::util::errors::{ObscuraError, ObscuraResult};
-use crate::vault::file::vault_exists;
+use crate::util::time::{age_seconds, format_age, short_date};
+use crate::vault::file::{decrypt_vault_with_dek, read_vault_file, vault_exists};
 use crate::vault::manager::{VaultManager, VaultType};
+use crate::vault::model::{AliasData, AliasKind, AliasesData};
+use crate::vault::namespace::{is_in_namespace, normalize_namespace, render_tree};
 use clap::Args;
+use serde::Serialize;
 use serde_json::{json, to_string_pretty};
 
 #[derive(Args)]
 pub struct ListArgs {
+    #[arg(help = "Only list aliases under this namespace (e.g. 'aws/prod/')")]
+    pub namespace: Option<String>,
+
     #[arg(long, short = 'g', help = "List entries from the global vault")]
     pub global: bool,
 
@@ -15,9 +23,69 @@ pub struct ListArgs {
 
     #[arg(long, help = "Render output as JSON")]
     pub json: bool,
+
+    #[arg(long, help = "Show namespaces as a tree")]
+    pub tree: bool,
+
+    #[arg(
+        long,
+        short = 'l',
+        help = "Show timestamps, age, value length and shadowing"
+    )]
+    pub long: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["global", "project"],
+        help = "List project vaults merged over the global vault, showing each alias's layer"
+    )]
+    pub layered: bool,
+}
+
+#[derive(Serialize)]
+struct LongEntry {
+    alias: String,
+    vault: String,
+    kind: AliasKind,
+    length: Option<usize>,
+    created_at: String,
+    rotated_at: Option<String>,
+    age_seconds: Option<i64>,
+    shadows: Option<String>,
+    shadowed_by: Option<String>,
+}
+
+impl LongEntry {
+    fn new(
+        alias: &str,
+        alias_data: &AliasData,
+        vault: String,
+        shadows: Option<String>,
+        shadowed_by: Option<String>,
+    ) -> Self {
+        let last_set = alias_data
+            .rotated_at
+            .as_ref()
+            .unwrap_or(&alias_data.created_at);
+        Self {
+            alias: alias.to_string(),
+            vault,
+            kind: alias_data.kind,
+            length: alias_data.value_len(),
+            created_at: alias_data.created_at.clone(),
+            rotated_at: alias_data.rotated_at.clone(),
+            age_seconds: age_seconds(last_set),
+            shadows,
+            shadowed_by,
+        }
+    }
 }
 
 pub fn handle_list(args: ListArgs) -> ObscuraResult<()> {
+    if args.layered {
+        return list_layered(&args);
+    }
+
     let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
 
     if !vault_exists(&vault_info.path) {
@@ -35,22 +103,56 @@ pub fn handle_list(args: ListArgs) -> ObscuraResult<()> {
     }
 
     let (_, aliases_data) = load_aliases(&vault_info.path)?;
-    let aliases = aliases_data.list_aliases();
-
-    let vault_type = match vault_info.vault_type {
-        VaultType::Global => "global",
-        VaultType::Project => "project",
+    let namespace = args.namespace.as_deref().map(normalize_namespace);
+    let aliases = match &namespace {
+        Some(namespace) => aliases_data.list_namespace(namespace),
+        None => aliases_data.list_aliases(),
     };
 
+    let vault_type = vault_scope(vault_info.vault_type);
+
+    if args.long {
+        let entries = long_entries(&aliases, &aliases_data, vault_info.vault_type)?;
+        if args.json {
+            println!(
+                "{}",
+                to_string_pretty(&json!({
+                    "vault_type": vault_type,
+                    "namespace": namespace,
+                    "aliases": entries
+                }))?
+            );
+        } else {
+            match &namespace {
+                Some(namespace) => println!("Listing {} vault ({}):", vault_type, namespace),
+                None => println!("Listing {} vault:", vault_type),
+            }
+            print_long(&entries);
+        }
+        return Ok(());
+    }
+
     if args.json {
-        println!("{}", to_string_pretty(&json!({ 
-            "vault_type": vault_type,
-            "aliases": aliases 
-        }))?);
+        println!(
+            "{}",
+            to_string_pretty(&json!({
+                "vault_type": vault_type,
+                "namespace": namespace,
+                "aliases": aliases
+            }))?
+        );
     } else {
-        println!("Listing {} vault:", vault_type);
+        match &namespace {
+            Some(namespace) => println!("Listing {} vault ({}):", vault_type, namespace),
+            None => println!("Listing {} vault:", vault_type),
+        }
         if aliases.is_empty() {
             println!("  (no aliases found)");
+        } else if args.tree || namespace.is_some() {
+            let root = namespace.unwrap_or_default();
+            for line in render_tree(&aliases, &root) {
+                println!("  {}", line);
+            }
         } else {
             for alias in aliases {
                 println!("  {}", alias);
@@ -60,3 +162,230 @@ pub fn handle_list(args: ListArgs) -> ObscuraResult<()> {
 
     Ok(())
 }
+
+fn long_entries(
+    aliases: &[String],
+    aliases_data: &AliasesData,
+    vault_type: VaultType,
+) -> ObscuraResult<Vec<LongEntry>> {
+    // Shadowing needs the alias names of the other vault; it is only shown
+    // when that vault's key is already cached, so listing never prompts twice.
+    let other_type = match vault_type {
+        VaultType::Global => VaultType::Project,
+        VaultType::Project => VaultType::Global,
+    };
+    let other_info = VaultManager::resolve_vault(
+        other_type == VaultType::Global,
+        other_type == VaultType::Project,
+    )?;
+    let other_aliases = if vault_exists(&other_info.path) {
+        match SessionStore::fetch_dek(&other_info.path)? {
+            Some(dek) => Some(decrypt_vault_with_dek(
+                &read_vault_file(&other_info.path)?,
+                &dek,
+            )?),
+            None => {
+                eprintln!(
+                    "Shadowing not shown: the {} vault is locked (run 'obscura unlock')",
+                    vault_scope(other_type)
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let mut entries = Vec::new();
+    for alias in aliases {
+        let alias_data = match aliases_data.aliases.get(alias) {
+            Some(alias_data) => alias_data,
+            None => continue,
+        };
+        let in_other = other_aliases
+            .as_ref()
+            .is_some_and(|other| other.aliases.contains_key(alias));
+
+        entries.push(LongEntry::new(
+            alias,
+            alias_data,
+            vault_scope(vault_type).to_string(),
+            (in_other && vault_type == VaultType::Project).then(|| "global".to_string()),
+            (in_other && vault_type == VaultType::Global).then(|| "project".to_string()),
+        ));
+    }
+    Ok(entries)
+}
+
+fn list_layered(args: &ListArgs) -> ObscuraResult<()> {
+    let mut resolver = SecretResolver::layered(VaultManager::resolve_layers()?)?;
+    let namespace = args.namespace.as_deref().map(normalize_namespace);
+    let labels: Vec<String> = (0..resolver.layer_count())
+        .map(|index| resolver.layer_label(index))
+        .collect();
+
+    // Each visible alias with its layer and the next layer down it hides.
+    let mut rows = Vec::new();
+    for (alias, index) in resolver.entries()? {
+        if let Some(namespace) = &namespace {
+            if !is_in_namespace(&alias, namespace) {
+                continue;
+            }
+        }
+        let mut shadows = None;
+        for lower in index + 1..resolver.layer_count() {
+            if resolver.layer(lower)?.aliases.aliases.contains_key(&alias) {
+                shadows = Some(lower);
+                break;
+            }
+        }
+        rows.push((alias, index, shadows));
+    }
+
+    if args.long {
+        let mut entries = Vec::new();
+        for (alias, index, shadows) in &rows {
+            let vault = resolver.layer(*index)?;
+            if let Some(alias_data) = vault.aliases.aliases.get(alias) {
+                entries.push(LongEntry::new(
+                    alias,
+                    alias_data,
+                    labels[*index].clone(),
+                    shadows.map(|lower| labels[lower].clone()),
+                    None,
+                ));
+            }
+        }
+        if args.json {
+            println!(
+                "{}",
+                to_string_pretty(&json!({
+                    "layers": labels,
+                    "namespace": namespace,
+                    "aliases": entries
+                }))?
+            );
+        } else {
+            println!("Listing layered vaults ({}):", labels.join(" -> "));
+            print_long(&entries);
+        }
+        return Ok(());
+    }
+
+    if args.json {
+        let aliases: Vec<_> = rows
+            .iter()
+            .map(|(alias, index, shadows)| {
+                json!({
+                    "alias": alias,
+                    "layer": labels[*index],
+                    "shadows": shadows.map(|lower| &labels[lower])
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            to_string_pretty(&json!({
+                "layers": labels,
+                "namespace": namespace,
+                "aliases": aliases
+            }))?
+        );
+        return Ok(());
+    }
+
+    println!("Listing layered vaults ({}):", labels.join(" -> "));
+    if rows.is_empty() {
+        println!("  (no aliases found)");
+        return Ok(());
+    }
+    let width = rows
+        .iter()
+        .map(|(alias, _, _)| alias.len())
+        .max()
+        .unwrap_or(0);
+    for (alias, index, shadows) in &rows {
+        match shadows {
+            Some(lower) => println!(
+                "  {:<width$}  {} (shadows {})",
+                alias,
+                labels[*index],
+                labels[*lower],
+                width = width
+            ),
+            None => println!("  {:<width$}  {}", alias, labels[*index], width = width),
+        }
+    }
+    Ok(())
+}
+
+fn print_long(entries: &[LongEntry]) {
+    if entries.is_empty() {
+        println!("  (no aliases found)");
+        return;
+    }
+
+    let width = entries
+        .iter()
+        .map(|entry| entry.alias.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let vault_width = entries
+        .iter()
+        .map(|entry| entry.vault.len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    println!(
+        "  {:<width$}  {:<vault_width$}  {:<4}  {:>6}  {:<10}  {:<10}  {:>4}  NOTE",
+        "ALIAS",
+        "VAULT",
+        "KIND",
+        "LENGTH",
+        "CREATED",
+        "ROTATED",
+        "AGE",
+        width = width,
+        vault_width = vault_width
+    );
+    for entry in entries {
+        let note = match (&entry.shadows, &entry.shadowed_by) {
+            (Some(other), _) => format!("shadows {}", other),
+            (_, Some(other)) => format!("shadowed by {}", other),
+            _ => String::new(),
+        };
+        let line = format!(
+            "  {:<width$}  {:<vault_width$}  {:<4}  {:>6}  {:<10}  {:<10}  {:>4}  {}",
+            entry.alias,
+            entry.vault,
+            kind_label(entry.kind),
+            entry
+                .length
+                .map(|length| length.to_string())
+                .unwrap_or_else(|| "?".to_string()),
+            short_date(&entry.created_at),
+            entry
+                .rotated_at
+                .as_deref()
+                .map(short_date)
+                .unwrap_or("never"),
+            entry
+                .age_seconds
+                .map(format_age)
+                .unwrap_or_else(|| "?".to_string()),
+            note,
+            width = width,
+            vault_width = vault_width
+        );
+        println!("{}", line.trim_end());
+    }
+}
+
+pub fn kind_label(kind: AliasKind) -> &'static str {
+    match kind {
+        AliasKind::Text => "text",
+        AliasKind::File => "file",
+        AliasKind::Otp => "otp",
+    }
+}
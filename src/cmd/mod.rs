@@ -1,25 +1,49 @@
 pub mod add;
+pub mod check;
 pub mod common;
 pub mod delete;
+pub mod env;
 pub mod export_dotenv;
+pub mod generate;
 pub mod get;
+pub mod git;
 pub mod import;
 pub mod init;
 pub mod list;
 pub mod lock;
+pub mod otp;
+pub mod projects;
 pub mod remove;
+pub mod render;
 pub mod rotate;
 pub mod run;
+pub mod search;
+pub mod shell;
+pub mod status;
+pub mod tag;
+pub mod transfer;
 pub mod unlock;
 pub use add::handle_add;
+pub use check::handle_check;
 pub use delete::handle_delete;
+pub use env::handle_env;
 pub use export_dotenv::handle_export_dotenv;
+pub use generate::handle_generate;
 pub use get::handle_get;
+pub use git::{handle_git_merge_driver, handle_git_textconv};
 pub use import::handle_import;
 pub use init::handle_init;
 pub use list::handle_list;
 pub use lock::handle_lock;
+pub use otp::handle_otp;
+pub use projects::handle_projects;
 pub use remove::handle_remove;
+pub use render::handle_render;
 pub use rotate::handle_rotate;
 pub use run::handle_run;
+pub use search::handle_search;
+pub use shell::handle_shell;
+pub use status::handle_status;
+pub use tag::handle_tag;
+pub use transfer::{handle_cp, handle_mv};
 pub use unlock::handle_unlock;
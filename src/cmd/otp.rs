@@ -0,0 +1,89 @@
+use crate::cmd::common::LoadedVault;
+use crate::crypto::otp::{decode_secret, hotp, totp};
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::manager::VaultManager;
+use crate::vault::model::OtpMethod;
+use clap::Args;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(Args)]
+pub struct OtpArgs {
+    #[arg(help = "Alias of the OTP seed")]
+    pub alias: String,
+
+    #[arg(long, short = 'g', help = "Read from the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Read from the project vault")]
+    pub project: bool,
+}
+
+pub struct OtpCode {
+    pub code: String,
+    pub remaining: Option<u64>,
+}
+
+pub fn handle_otp(args: OtpArgs) -> ObscuraResult<()> {
+    let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
+    let mut vault = LoadedVault::load(vault_info)?;
+
+    let otp = next_code(&mut vault, &args.alias)?;
+    println!("{}", otp.code);
+    if let Some(remaining) = otp.remaining {
+        let unit = if remaining == 1 { "second" } else { "seconds" };
+        eprintln!("Valid for {} more {}", remaining, unit);
+    }
+
+    Ok(())
+}
+
+// HOTP counters are persisted before the code is returned so a code is never
+// handed out twice.
+pub fn next_code(vault: &mut LoadedVault, alias: &str) -> ObscuraResult<OtpCode> {
+    let params = vault
+        .aliases
+        .aliases
+        .get(alias)
+        .ok_or_else(|| ObscuraError::AliasNotFound(alias.to_string()))?
+        .otp
+        .clone()
+        .ok_or_else(|| {
+            ObscuraError::CustomError(format!("Alias '{}' is not an OTP secret", alias))
+        })?;
+
+    let seed = vault
+        .aliases
+        .get_alias(alias, &vault.dek)?
+        .ok_or_else(|| ObscuraError::AliasNotFound(alias.to_string()))?;
+    let secret = decode_secret(&seed)?;
+
+    match params.method {
+        OtpMethod::Totp => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|_| ObscuraError::CustomError("System clock is invalid".to_string()))?
+                .as_secs();
+            let (code, remaining) = totp(&secret, now, &params)?;
+            Ok(OtpCode {
+                code,
+                remaining: Some(remaining),
+            })
+        }
+        OtpMethod::Hotp => {
+            let code = hotp(&secret, params.counter, &params)?;
+            if let Some(otp) = vault
+                .aliases
+                .aliases
+                .get_mut(alias)
+                .and_then(|alias_data| alias_data.otp.as_mut())
+            {
+                otp.counter = params.counter + 1;
+            }
+            vault.save()?;
+            Ok(OtpCode {
+                code,
+                remaining: None,
+            })
+        }
+    }
+}
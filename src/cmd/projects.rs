@@ -0,0 +1,541 @@
+use crate::session::SessionStore;
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::io::prompt_yes_no;
+use crate::util::paths::get_project_dir;
+use crate::util::time::{age_seconds, format_age};
+use crate::vault::identity::{
+    marker_path, project_identity, read_marker, write_marker, Marker, ProjectIdentity,
+};
+use crate::vault::manager::{
+    project_vault_files, ProjectRecord, ProjectStatus, VaultInfo, VaultManager,
+};
+use clap::{Args, Subcommand};
+use rand::{rngs::OsRng, RngCore};
+use serde_json::{json, to_string_pretty, Value};
+use std::path::{Path, PathBuf};
+
+#[derive(Subcommand)]
+pub enum ProjectsCommand {
+    #[command(about = "List project vaults (default)")]
+    List(ListProjectsArgs),
+
+    #[command(about = "Show details of a project vault")]
+    Inspect(InspectArgs),
+
+    #[command(about = "Move a project vault to a new project directory")]
+    Relocate(RelocateArgs),
+
+    #[command(about = "Delete orphaned project vaults, or one project vault by key")]
+    Prune(PruneArgs),
+
+    #[command(about = "Require confirmation before 'run' uses an environment")]
+    Protect(ProtectArgs),
+
+    #[command(about = "Identify this project by a marker file and attach its existing vault")]
+    Link(LinkArgs),
+}
+
+#[derive(Args, Default)]
+pub struct ListProjectsArgs {
+    #[arg(long, help = "Only show orphaned project vaults")]
+    pub orphans: bool,
+
+    #[arg(long, help = "Render output as JSON")]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct InspectArgs {
+    #[arg(help = "Project directory or vault key (default: the current project)")]
+    pub project: Option<String>,
+
+    #[arg(long, help = "Render output as JSON")]
+    pub json: bool,
+}
+
+#[derive(Args)]
+pub struct RelocateArgs {
+    #[arg(help = "Directory (or vault key) the vault currently belongs to")]
+    pub old: String,
+
+    #[arg(help = "Directory the vault should belong to")]
+    pub new: String,
+
+    #[arg(long, help = "Skip the confirmation prompt")]
+    pub yes: bool,
+}
+
+#[derive(Args)]
+pub struct ProtectArgs {
+    #[arg(help = "Environment of the current project")]
+    pub environment: String,
+
+    #[arg(long, help = "Remove the protection instead")]
+    pub off: bool,
+}
+
+#[derive(Args)]
+pub struct PruneArgs {
+    #[arg(
+        help = "Vault key (or unique prefix) or project directory to delete, whatever its status"
+    )]
+    pub project: Option<String>,
+
+    #[arg(
+        long,
+        required_unless_present = "project",
+        conflicts_with = "project",
+        help = "Delete path-keyed vaults whose project directory no longer exists"
+    )]
+    pub orphans: bool,
+
+    #[arg(long, help = "Skip the confirmation prompt")]
+    pub yes: bool,
+}
+
+#[derive(Args)]
+pub struct LinkArgs {
+    #[arg(
+        long,
+        conflicts_with = "git_remote",
+        help = "Project ID to write to the .obscura marker (random if omitted)"
+    )]
+    pub id: Option<String>,
+
+    #[arg(
+        long,
+        num_args = 0..=1,
+        default_missing_value = "origin",
+        value_name = "REMOTE",
+        help = "Identify the project by the URL of this git remote"
+    )]
+    pub git_remote: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        help = "Directory whose path-keyed vault should be attached (default: current directory)"
+    )]
+    pub from: Option<String>,
+}
+
+pub fn handle_projects(command: Option<ProjectsCommand>) -> ObscuraResult<()> {
+    match command.unwrap_or(ProjectsCommand::List(ListProjectsArgs::default())) {
+        ProjectsCommand::List(args) => list_projects(args),
+        ProjectsCommand::Inspect(args) => inspect_project(args),
+        ProjectsCommand::Relocate(args) => relocate_project(args),
+        ProjectsCommand::Prune(args) => prune_projects(args),
+        ProjectsCommand::Protect(args) => protect_environment(args),
+        ProjectsCommand::Link(args) => link_project(args),
+    }
+}
+
+fn list_projects(args: ListProjectsArgs) -> ObscuraResult<()> {
+    let records: Vec<ProjectRecord> = VaultManager::list_projects()?
+        .into_iter()
+        .filter(|record| !args.orphans || record.is_orphan())
+        .collect();
+
+    if args.json {
+        let projects: Vec<Value> = records.iter().map(record_json).collect();
+        println!("{}", to_string_pretty(&json!({ "projects": projects }))?);
+        return Ok(());
+    }
+
+    if records.is_empty() {
+        println!("No project vaults found");
+        return Ok(());
+    }
+
+    let width = records
+        .iter()
+        .map(|record| record.path().unwrap_or("(unknown)").len())
+        .max()
+        .unwrap_or(0);
+    for record in &records {
+        let meta = record.meta.as_ref();
+        let line = format!(
+            "  {}  {:<width$}  created {:>4}  last used {:>4}  {}",
+            short_key(record),
+            record.path().unwrap_or("(unknown)"),
+            meta.map(|meta| ago(&meta.created_at))
+                .unwrap_or_else(|| "?".to_string()),
+            meta.map(|meta| ago(&meta.last_used))
+                .unwrap_or_else(|| "?".to_string()),
+            status_note(record),
+            width = width
+        );
+        println!("{}", line.trim_end());
+    }
+    Ok(())
+}
+
+fn inspect_project(args: InspectArgs) -> ObscuraResult<()> {
+    let record = match &args.project {
+        Some(target) => find_record(target)?,
+        None => {
+            let current_dir =
+                std::env::current_dir().map_err(|_| ObscuraError::FilePermissionError)?;
+            match VaultManager::find_project_vault(&current_dir)? {
+                Some(vault_info) => find_record(&vault_key(&vault_info).unwrap_or_default())?,
+                // Projects with only environment vaults.
+                None => find_record(&current_dir.to_string_lossy())?,
+            }
+        }
+    };
+
+    if args.json {
+        println!("{}", to_string_pretty(&record_json(&record))?);
+        return Ok(());
+    }
+
+    let vault_path = record.vault_path();
+    println!("Key:        {}", record.key);
+    match &record.meta {
+        Some(meta) => {
+            println!("Path:       {}", meta.path);
+            if let Some(identity) = &meta.identity {
+                println!("Identity:   {}", identity);
+            }
+            let environments: Vec<String> = record
+                .environments()
+                .into_iter()
+                .map(|environment| {
+                    if meta.protected_environments.contains(&environment) {
+                        format!("{} (protected)", environment)
+                    } else {
+                        environment
+                    }
+                })
+                .collect();
+            if !environments.is_empty() {
+                println!("Envs:       {}", environments.join(", "));
+            }
+            println!(
+                "Created:    {} ({} ago)",
+                meta.created_at,
+                ago(&meta.created_at)
+            );
+            println!(
+                "Last used:  {} ({} ago)",
+                meta.last_used,
+                ago(&meta.last_used)
+            );
+        }
+        None => println!("Path:       (unknown, meta.json is missing or unreadable)"),
+    }
+    match std::fs::metadata(&vault_path) {
+        Ok(metadata) => {
+            let unlocked = SessionStore::fetch_dek(&vault_path)?.is_some();
+            println!(
+                "Vault:      {} ({} bytes)",
+                vault_path.display(),
+                metadata.len()
+            );
+            println!(
+                "State:      {}",
+                if unlocked { "unlocked" } else { "locked" }
+            );
+        }
+        Err(_) if record.environments().is_empty() => println!("Vault:      missing"),
+        Err(_) => println!("Vault:      none (environments only)"),
+    }
+    match record.status() {
+        ProjectStatus::Orphan => println!("Orphaned:   the project directory no longer exists"),
+        ProjectStatus::Unknown => println!(
+            "Status:     unknown; the vault may still be used elsewhere (delete with 'obscura projects prune {}')",
+            short_key(&record)
+        ),
+        ProjectStatus::Active => {}
+    }
+    Ok(())
+}
+
+fn relocate_project(args: RelocateArgs) -> ObscuraResult<()> {
+    let record = find_record(&args.old)?;
+    let new_dir = PathBuf::from(&args.new)
+        .canonicalize()
+        .map_err(|_| ObscuraError::CustomError(format!("Directory '{}' not found", args.new)))?;
+    if !new_dir.is_dir() {
+        return Err(ObscuraError::CustomError(format!(
+            "'{}' is not a directory",
+            args.new
+        )));
+    }
+
+    if !args.yes {
+        let message = format!(
+            "Move the vault of {} to {}?",
+            record.path().unwrap_or(&record.key),
+            new_dir.display()
+        );
+        if !prompt_yes_no(&message)? {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    clear_sessions(&record.vault_files())?;
+    VaultManager::attach_project_vault(&record.key, &new_dir)?;
+    println!("Relocated project vault to {}", new_dir.display());
+    Ok(())
+}
+
+fn protect_environment(args: ProtectArgs) -> ObscuraResult<()> {
+    let current_dir = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+    // A project may only have environment vaults, so fall back to the
+    // current directory when no default vault is found.
+    let project_dir = VaultManager::find_project_vault(&current_dir)?
+        .and_then(|vault_info| vault_info.project_dir)
+        .unwrap_or(current_dir);
+
+    VaultManager::set_protected(&project_dir, &args.environment, !args.off)?;
+    if args.off {
+        println!("Environment '{}' is no longer protected", args.environment);
+    } else {
+        println!("Environment '{}' is now protected", args.environment);
+    }
+    Ok(())
+}
+
+fn prune_projects(args: PruneArgs) -> ObscuraResult<()> {
+    if let Some(target) = &args.project {
+        return prune_project(find_record(target)?, args.yes);
+    }
+
+    let (orphans, unknown): (Vec<ProjectRecord>, Vec<ProjectRecord>) =
+        VaultManager::list_projects()?
+            .into_iter()
+            .filter(|record| record.status() != ProjectStatus::Active)
+            .partition(ProjectRecord::is_orphan);
+
+    if !unknown.is_empty() {
+        println!(
+            "Skipping {} project vault(s) with unknown status; delete one with 'obscura projects prune KEY':",
+            unknown.len()
+        );
+        for record in &unknown {
+            println!(
+                "  {}  {}",
+                short_key(record),
+                record.path().unwrap_or("(unknown)")
+            );
+        }
+    }
+
+    if orphans.is_empty() {
+        println!("No orphaned project vaults");
+        return Ok(());
+    }
+
+    println!("Orphaned project vaults:");
+    for record in &orphans {
+        println!(
+            "  {}  {}",
+            short_key(record),
+            record.path().unwrap_or("(unknown)")
+        );
+    }
+
+    if !args.yes {
+        let message = format!(
+            "Delete {} orphaned project vault(s)? This cannot be undone.",
+            orphans.len()
+        );
+        if !prompt_yes_no(&message)? {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    for record in &orphans {
+        clear_sessions(&record.vault_files())?;
+        VaultManager::remove_project(record)?;
+    }
+    println!("Deleted {} orphaned project vault(s)", orphans.len());
+    Ok(())
+}
+
+fn prune_project(record: ProjectRecord, yes: bool) -> ObscuraResult<()> {
+    if !yes {
+        let message = format!(
+            "Delete the project vault {} ({}) and its environments? This cannot be undone.",
+            short_key(&record),
+            record.path().unwrap_or("unknown directory")
+        );
+        if !prompt_yes_no(&message)? {
+            println!("Cancelled");
+            return Ok(());
+        }
+    }
+
+    clear_sessions(&record.vault_files())?;
+    VaultManager::remove_project(&record)?;
+    println!("Deleted project vault {}", short_key(&record));
+    Ok(())
+}
+
+// Cached keys are stored per vault path, which is about to change or go away.
+fn clear_sessions(vault_files: &[PathBuf]) -> ObscuraResult<()> {
+    for path in vault_files {
+        SessionStore::clear(Some(path))?;
+    }
+    Ok(())
+}
+
+// Accepts a project directory (existing or recorded in meta.json) or a
+// unique prefix of the vault key.
+fn find_record(target: &str) -> ObscuraResult<ProjectRecord> {
+    let records = VaultManager::list_projects()?;
+
+    let path = Path::new(target);
+    if path.is_dir() {
+        if let Ok(vault_info) = VaultInfo::project(path) {
+            let key = vault_key(&vault_info);
+            if let Some(position) = records
+                .iter()
+                .position(|record| Some(&record.key) == key.as_ref())
+            {
+                return Ok(records
+                    .into_iter()
+                    .nth(position)
+                    .expect("position is in range"));
+            }
+        }
+    }
+
+    let absolute = std::env::current_dir()
+        .map(|dir| dir.join(target).to_string_lossy().to_string())
+        .unwrap_or_else(|_| target.to_string());
+    let mut matches: Vec<ProjectRecord> = records
+        .into_iter()
+        .filter(|record| {
+            record.path() == Some(target)
+                || record.path() == Some(absolute.as_str())
+                || record.key.starts_with(target)
+        })
+        .collect();
+
+    match matches.len() {
+        0 => Err(ObscuraError::CustomError(format!(
+            "No project vault found for '{}'",
+            target
+        ))),
+        1 => Ok(matches.remove(0)),
+        _ => Err(ObscuraError::CustomError(format!(
+            "'{}' matches {} project vaults; use a longer key",
+            target,
+            matches.len()
+        ))),
+    }
+}
+
+fn vault_key(vault_info: &VaultInfo) -> Option<String> {
+    let dir = vault_info.path.parent()?;
+    // Environment vaults live in `<key>/envs/`.
+    let dir = match vault_info.environment {
+        Some(_) => dir.parent()?,
+        None => dir,
+    };
+    dir.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+fn record_json(record: &ProjectRecord) -> Value {
+    let meta = record.meta.as_ref();
+    json!({
+        "key": record.key,
+        "path": record.path(),
+        "identity": meta.and_then(|meta| meta.identity.as_deref()),
+        "created_at": meta.map(|meta| &meta.created_at),
+        "last_used": meta.map(|meta| &meta.last_used),
+        "has_vault": record.vault_path().exists(),
+        "environments": record.environments(),
+        "protected_environments": meta.map(|meta| &meta.protected_environments),
+        "orphan": record.is_orphan(),
+        "status": record.status().label(),
+    })
+}
+
+fn status_note(record: &ProjectRecord) -> &'static str {
+    match (record.status(), record.has_vaults()) {
+        (ProjectStatus::Orphan, _) => "[orphan]",
+        (ProjectStatus::Unknown, _) => "[unknown]",
+        (ProjectStatus::Active, false) => "[no vault]",
+        (ProjectStatus::Active, true) => "",
+    }
+}
+
+fn short_key(record: &ProjectRecord) -> &str {
+    &record.key[..record.key.len().min(12)]
+}
+
+fn ago(timestamp: &str) -> String {
+    age_seconds(timestamp)
+        .map(format_age)
+        .unwrap_or_else(|| "?".to_string())
+}
+
+fn link_project(args: LinkArgs) -> ObscuraResult<()> {
+    let current_dir = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+
+    let requested = match (args.id, args.git_remote) {
+        (Some(id), _) => Some(Marker::Id(id)),
+        (None, Some(remote)) => Some(Marker::GitRemote(remote)),
+        (None, None) => None,
+    };
+    match (read_marker(&current_dir)?, requested) {
+        (Some(existing), Some(requested)) if existing != requested => {
+            return Err(ObscuraError::CustomError(format!(
+                "{} already sets a different identity; edit or remove it first",
+                marker_path(&current_dir).display()
+            )));
+        }
+        (Some(_), _) => {}
+        (None, requested) => {
+            let marker = requested.unwrap_or_else(|| {
+                let mut id = [0u8; 16];
+                OsRng.fill_bytes(&mut id);
+                Marker::Id(hex::encode(id))
+            });
+            write_marker(&current_dir, &marker)?;
+            println!("Wrote {}", marker_path(&current_dir).display());
+        }
+    }
+
+    let identity = project_identity(&current_dir)?;
+    let source = match &args.from {
+        // The old checkout may no longer exist, so fall back to the path as given.
+        Some(from) => {
+            let from = current_dir.join(from);
+            from.canonicalize().unwrap_or(from)
+        }
+        None => current_dir.clone(),
+    };
+    let source_key = ProjectIdentity::Path(source.clone()).key();
+    let source_vaults = project_vault_files(&get_project_dir(&source_key)?);
+
+    if source_key == identity.key() || source_vaults.is_empty() {
+        if VaultInfo::project(&current_dir)?.path.exists() {
+            println!("Project is linked to {}", identity);
+            return Ok(());
+        }
+        if args.from.is_some() {
+            return Err(ObscuraError::VaultNotFound);
+        }
+        println!(
+            "No vault to attach yet; 'obscura init' will create one for {}",
+            identity
+        );
+        return Ok(());
+    }
+
+    clear_sessions(&source_vaults)?;
+    VaultManager::attach_project_vault(&source_key, &current_dir)?;
+    println!("Linked the vault of {} to {}", source.display(), identity);
+    Ok(())
+}
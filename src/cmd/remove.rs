@@ -3,11 +3,12 @@ use crate::util::errors::{ObscuraError, ObscuraResult};
 use crate::util::io::prompt_yes_no;
 use crate::vault::file::{encrypt_and_save_vault, vault_exists};
 use crate::vault::manager::VaultManager;
+use crate::vault::namespace::normalize_namespace;
 use clap::Args;
 
 #[derive(Args)]
 pub struct RemoveArgs {
-    #[arg(help = "Alias name to remove")]
+    #[arg(help = "Alias name to remove (or namespace with --recursive)")]
     pub alias: String,
 
     #[arg(long, short = 'g', help = "Remove from the global vault")]
@@ -18,6 +19,13 @@ pub struct RemoveArgs {
 
     #[arg(long, help = "Skip the confirmation prompt")]
     pub yes: bool,
+
+    #[arg(
+        long,
+        short = 'r',
+        help = "Remove every alias under the given namespace"
+    )]
+    pub recursive: bool,
 }
 
 pub fn handle_remove(args: RemoveArgs) -> ObscuraResult<()> {
@@ -29,15 +37,50 @@ pub fn handle_remove(args: RemoveArgs) -> ObscuraResult<()> {
 
     let (dek, mut aliases_data, vault_file) = load_vault(&vault_info.path)?;
 
+    if args.recursive {
+        let namespace = normalize_namespace(&args.alias);
+        if namespace.is_empty() {
+            return Err(ObscuraError::CustomError(
+                "Refusing to remove every alias; use 'obscura delete' to delete the vault"
+                    .to_string(),
+            ));
+        }
+
+        let aliases = aliases_data.list_namespace(&namespace);
+        if aliases.is_empty() {
+            return Err(ObscuraError::AliasNotFound(namespace));
+        }
+
+        if !args.yes {
+            for alias in &aliases {
+                println!("  {}", alias);
+            }
+            if !prompt_yes_no(&format!(
+                "Remove {} aliases under '{}'?",
+                aliases.len(),
+                namespace
+            ))? {
+                println!("Cancelled");
+                return Ok(());
+            }
+        }
+
+        for alias in &aliases {
+            aliases_data.remove_alias(alias);
+        }
+        encrypt_and_save_vault(&vault_info.path, &vault_file, &aliases_data, &dek)?;
+
+        println!("Removed {} aliases under '{}'", aliases.len(), namespace);
+        return Ok(());
+    }
+
     if !aliases_data.aliases.contains_key(&args.alias) {
         return Err(ObscuraError::AliasNotFound(args.alias));
     }
 
-    if !args.yes {
-        if !prompt_yes_no(&format!("Remove alias '{}'?", args.alias))? {
-            println!("Cancelled");
-            return Ok(());
-        }
+    if !args.yes && !prompt_yes_no(&format!("Remove alias '{}'?", args.alias))? {
+        println!("Cancelled");
+        return Ok(());
     }
 
     aliases_data.remove_alias(&args.alias);
@@ -0,0 +1,132 @@
+use crate::cmd::common::SecretResolver;
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::io::write_private_file_atomically;
+use crate::vault::template::Template;
+use clap::Args;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Args)]
+pub struct RenderArgs {
+    #[arg(help = "Template file with {{ ALIAS }} placeholders")]
+    pub template: PathBuf,
+
+    #[arg(
+        long,
+        short = 'o',
+        value_name = "PATH",
+        help = "Write to this file (mode 0600) instead of stdout"
+    )]
+    pub output: Option<PathBuf>,
+
+    #[arg(long, requires = "output", help = "Allow overwriting the output file")]
+    pub overwrite: bool,
+
+    #[arg(long, short = 'g', help = "Use the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Use the project vault")]
+    pub project: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["global", "project"],
+        help = "Use project vaults merged over the global vault"
+    )]
+    pub layered: bool,
+}
+
+pub fn handle_render(args: RenderArgs) -> ObscuraResult<()> {
+    if let Some(output) = &args.output {
+        if fs::symlink_metadata(output).is_ok() && !args.overwrite {
+            return Err(ObscuraError::FileExists(output.display().to_string()));
+        }
+    }
+
+    let mut resolver = SecretResolver::open(args.layered, args.global, args.project)?;
+    let rendered = render_template(&args.template, &mut resolver, &mut Vec::new())?;
+
+    match &args.output {
+        Some(output) => {
+            write_private_file_atomically(output, &rendered)?;
+            eprintln!("Rendered {}", output.display());
+        }
+        None => {
+            let mut stdout = io::stdout();
+            stdout.write_all(&rendered)?;
+            stdout.flush()?;
+        }
+    }
+    Ok(())
+}
+
+// Renders the template at `path`, adding every value used to `values`.
+pub fn render_template(
+    path: &Path,
+    resolver: &mut SecretResolver,
+    values: &mut Vec<(String, Vec<u8>)>,
+) -> ObscuraResult<Vec<u8>> {
+    let text = fs::read_to_string(path).map_err(|err| {
+        ObscuraError::CustomError(format!("Cannot read template {}: {}", path.display(), err))
+    })?;
+    let in_template =
+        |err: ObscuraError| ObscuraError::CustomError(format!("{}: {}", path.display(), err));
+
+    Template::parse(&text)
+        .and_then(|template| {
+            template.render(|alias| {
+                let value = resolver.value_bytes(alias)?;
+                if let Some(value) = &value {
+                    values.push((alias.to_string(), value.clone()));
+                }
+                Ok(value)
+            })
+        })
+        .map_err(in_template)
+}
+
+// A file rendered for `run --render`, removed again when dropped.
+pub struct RenderedFile {
+    path: Option<PathBuf>,
+}
+
+impl RenderedFile {
+    pub fn write(
+        template: &Path,
+        output: &Path,
+        replace: bool,
+        resolver: &mut SecretResolver,
+        values: &mut Vec<(String, Vec<u8>)>,
+    ) -> ObscuraResult<Self> {
+        // The file is deleted afterwards, so an existing one would be lost.
+        if !replace && fs::symlink_metadata(output).is_ok() {
+            return Err(ObscuraError::CustomError(format!(
+                "{} already exists and would be deleted when the command exits; pass --render-overwrite to replace it anyway",
+                output.display()
+            )));
+        }
+        let rendered = render_template(template, resolver, values)?;
+        write_private_file_atomically(output, &rendered)?;
+        Ok(Self {
+            path: Some(output.to_path_buf()),
+        })
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    // For a file that a newer render has already replaced.
+    pub fn keep(mut self) {
+        self.path = None;
+    }
+}
+
+impl Drop for RenderedFile {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
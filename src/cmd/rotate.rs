@@ -1,9 +1,13 @@
 use crate::cmd::common::load_vault;
+use crate::crypto::generate::{generate_secret, DEFAULT_SPEC};
+use crate::crypto::otp::parse_seed;
 use crate::util::errors::{ObscuraError, ObscuraResult};
-use crate::util::io::prompt_secret_value;
+use crate::util::io::{prompt_secret_value, read_secret_file};
 use crate::vault::file::{encrypt_and_save_vault, vault_exists};
 use crate::vault::manager::VaultManager;
+use crate::vault::model::AliasKind;
 use clap::Args;
+use std::path::PathBuf;
 
 #[derive(Args)]
 pub struct RotateArgs {
@@ -15,6 +19,23 @@ pub struct RotateArgs {
 
     #[arg(long, short = 'p', help = "Rotate in the project vault")]
     pub project: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Replace the value with the raw contents of a file"
+    )]
+    pub from_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "SPEC",
+        num_args = 0..=1,
+        default_missing_value = DEFAULT_SPEC,
+        conflicts_with = "from_file",
+        help = "Generate a random value instead of prompting (see 'obscura generate')"
+    )]
+    pub generate: Option<String>,
 }
 
 pub fn handle_rotate(args: RotateArgs) -> ObscuraResult<()> {
@@ -30,8 +51,25 @@ pub fn handle_rotate(args: RotateArgs) -> ObscuraResult<()> {
         return Err(ObscuraError::AliasNotFound(args.alias));
     }
 
-    let new_value = prompt_secret_value(&args.alias)?;
-    aliases_data.rotate_alias(&args.alias, new_value, &dek)?;
+    let (new_value, kind) = match (&args.from_file, &args.generate) {
+        (Some(path), _) => (read_secret_file(path)?, AliasKind::File),
+        (None, Some(spec)) => (generate_secret(spec)?.into_bytes(), AliasKind::Text),
+        (None, None) => (
+            prompt_secret_value(&args.alias)?.into_bytes(),
+            AliasKind::Text,
+        ),
+    };
+    if args.generate.is_none()
+        && kind == AliasKind::Text
+        && aliases_data.alias_kind(&args.alias) == Some(AliasKind::Otp)
+    {
+        let input = String::from_utf8(new_value)
+            .map_err(|_| ObscuraError::CustomError("OTP seed must be text".to_string()))?;
+        let seed = parse_seed(&input)?;
+        aliases_data.rotate_otp_alias(&args.alias, seed.secret, seed.params, &dek)?;
+    } else {
+        aliases_data.rotate_alias_bytes(&args.alias, &new_value, kind, &dek)?;
+    }
     encrypt_and_save_vault(&vault_info.path, &vault_file, &aliases_data, &dek)?;
 
     println!("Rotated alias '{}'", args.alias);
@@ -1,13 +1,45 @@
-use crate::cmd::common::load_aliases;
+use crate::cmd::common::SecretResolver;
+use crate::cmd::otp::next_code;
+use crate::cmd::render::RenderedFile;
 use crate::util::errors::{ObscuraError, ObscuraResult};
-use crate::vault::file::vault_exists;
+use crate::util::io::prompt_yes_no;
+use crate::util::pattern::{glob_match, has_wildcards};
+use crate::util::process::{exit_like, parse_signal, spawn_forwarding_signals, RunningChild};
+use crate::util::redact::Redactor;
+use crate::util::secret_pipe::SecretPipe;
+use crate::util::tempdir::PrivateTempDir;
+use crate::util::watch::FileWatcher;
 use crate::vault::manager::VaultManager;
+use crate::vault::manifest::Manifest;
+use crate::vault::model::AliasKind;
+use crate::vault::namespace::{
+    env_name_in_namespace, is_in_namespace, is_namespaced, normalize_namespace,
+};
+use crate::vault::template::Template;
 use clap::Args;
 use std::collections::HashMap;
-use std::path::Path;
-use std::process::Command;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
 
-#[derive(Args)]
+// What `--clean-env` passes through besides the secrets and `--keep`.
+const CLEAN_ENV_VARS: &[&str] = &["PATH", "HOME", "TERM", "LANG"];
+#[cfg(windows)]
+const CLEAN_ENV_PLATFORM_VARS: &[&str] = &["SYSTEMROOT", "COMSPEC", "PATHEXT", "TEMP", "TMP"];
+#[cfg(not(windows))]
+const CLEAN_ENV_PLATFORM_VARS: &[&str] = &[];
+const OBSCURA_VAR_PREFIX: &str = "OBSCURA_";
+const UNBUFFERED_HINTS: &[(&str, &str)] = &[("PYTHONUNBUFFERED", "1")];
+// How often `--watch` checks whether the command exited.
+const WATCH_INTERVAL: Duration = Duration::from_millis(200);
+// How long a command stopped by `--watch` may take before it is killed.
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Args, Default)]
 pub struct RunArgs {
     #[arg(long, short = 'g', help = "Use the global vault")]
     pub global: bool,
@@ -15,8 +47,144 @@ pub struct RunArgs {
     #[arg(long, short = 'p', help = "Use the project vault")]
     pub project: bool,
 
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "NAMESPACE",
+        help = "Inject only aliases under this namespace, named by their leaf names"
+    )]
+    pub namespace: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_delimiter = ',',
+        help = "Inject only matching aliases (comma-separated names or globs)"
+    )]
+    pub only: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_delimiter = ',',
+        help = "Do not inject matching aliases (comma-separated names or globs)"
+    )]
+    pub exclude: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "ENV=ALIAS",
+        help = "Inject ALIAS as the variable ENV (repeatable)"
+    )]
+    pub map: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        help = "Prepend PREFIX to injected variable names"
+    )]
+    pub prefix: Option<String>,
+
+    #[arg(
+        long,
+        help = "Keep variables already set in the environment instead of overriding them"
+    )]
+    pub no_override: bool,
+
+    #[arg(long, help = "Ignore obscura.toml and inject every alias")]
+    pub no_manifest: bool,
+
+    #[arg(
+        long,
+        help = "Start the command with only PATH, HOME, TERM, LANG, --keep variables and the secrets"
+    )]
+    pub clean_env: bool,
+
+    #[arg(
+        long,
+        value_name = "VAR",
+        value_delimiter = ',',
+        help = "Pass this variable through (comma-separated names or globs)"
+    )]
+    pub keep: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Mask secret values in the command's output as ***ALIAS***"
+    )]
+    pub redact: bool,
+
+    #[arg(
+        long,
+        help = "Replace {{ALIAS}} in the command's arguments with the secret value"
+    )]
+    pub template_args: bool,
+
+    #[arg(
+        long,
+        value_name = "ALIAS",
+        help = "Write this secret to the command's standard input"
+    )]
+    pub stdin: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "ALIAS=ENVVAR",
+        help = "Pass ALIAS through a pipe whose path is set in ENVVAR (repeatable)"
+    )]
+    pub as_file: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE:OUT",
+        help = "Render TEMPLATE to OUT before starting, deleting OUT afterwards (repeatable)"
+    )]
+    pub render: Vec<String>,
+
+    #[arg(
+        long,
+        requires = "render",
+        help = "Let --render replace an existing OUT, which is still deleted afterwards"
+    )]
+    pub render_overwrite: bool,
+
+    #[arg(long, help = "Restart the command when the vault changes")]
+    pub watch: bool,
+
+    #[arg(
+        long,
+        value_name = "SIGNAL",
+        default_value = "TERM",
+        requires = "watch",
+        help = "Signal that stops the command before a restart"
+    )]
+    pub stop_signal: String,
+
+    #[arg(
+        long,
+        value_name = "MS",
+        default_value_t = 500,
+        requires = "watch",
+        help = "Wait until the vault has been unchanged this long before restarting"
+    )]
+    pub debounce: u64,
+
+    #[arg(long, help = "Skip the confirmation for protected environments")]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["global", "project"],
+        help = "Inject project vaults merged over the global vault"
+    )]
+    pub layered: bool,
+
     #[arg(help = "Command and arguments to execute (after --)")]
     pub command: Vec<String>,
+
+    // Set on top of the secrets, e.g. the marker of `obscura shell`.
+    #[arg(skip)]
+    pub extra_env: Vec<(String, String)>,
 }
 
 pub fn handle_run(args: RunArgs) -> ObscuraResult<()> {
@@ -24,36 +192,636 @@ pub fn handle_run(args: RunArgs) -> ObscuraResult<()> {
         return Err(ObscuraError::InvalidVaultFormat);
     }
 
-    let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
+    if !args.global && !args.yes {
+        let vault_info = VaultManager::resolve_vault(false, args.project)?;
+        if VaultManager::is_protected(&vault_info) {
+            let message = format!(
+                "Run '{}' with secrets from the protected '{}' environment?",
+                args.command.join(" "),
+                vault_info.environment.as_deref().unwrap_or_default()
+            );
+            if !prompt_yes_no(&message)? {
+                println!("Cancelled");
+                return Ok(());
+            }
+        }
+    }
+
+    let selection = Selection::open(&args)?;
+    let mut resolver = SecretResolver::open(args.layered, args.global, args.project)?;
+
+    let mut launch = Launch::prepare(&args, &selection, &mut resolver, &[])?;
+    if args.watch {
+        return watch(&args, &selection, resolver, launch);
+    }
+
+    // Nothing to feed, filter or clean up afterwards, so the command can take
+    // over this process.
+    #[cfg(unix)]
+    if launch.can_exec(args.redact) {
+        return Err(crate::util::process::exec(&mut launch.cmd));
+    }
+
+    let result = launch.start(args.redact).and_then(Started::wait);
+
+    // process::exit skips destructors, so wipe materialized files explicitly.
+    drop(launch);
+    exit_like(result?);
+}
+
+// One start of the command, built from the current vault contents.
+struct Launch {
+    cmd: Command,
+    stdin: Option<Vec<u8>>,
+    pipes: Vec<SecretPipe>,
+    values: Vec<(String, Vec<u8>)>,
+    // Wiped or removed when the launch is dropped, after the command has exited.
+    secret_dir: Option<PrivateTempDir>,
+    rendered: Vec<RenderedFile>,
+}
+
+impl Launch {
+    // `previous` are the files rendered for the launch being replaced, which
+    // may be overwritten.
+    fn prepare(
+        args: &RunArgs,
+        selection: &Selection,
+        resolver: &mut SecretResolver,
+        previous: &[RenderedFile],
+    ) -> ObscuraResult<Self> {
+        let mut secret_dir = None;
+        let mut values = Vec::new();
+        let mut env_vars =
+            get_secrets_as_env_vars(resolver, selection, &mut secret_dir, &mut values)?;
+
+        let mut pipes = Vec::new();
+        for (alias, env_name) in &selection.as_file {
+            let value = secret_bytes(resolver, alias)?;
+            values.push((alias.clone(), value.clone()));
+            let pipe = SecretPipe::create(alias, value, &mut secret_dir)?;
+            env_vars.insert(env_name.clone(), pipe.path().to_string_lossy().to_string());
+            pipes.push(pipe);
+        }
+
+        let inherited = inherited_vars(args.clean_env, &args.keep);
+        if args.no_override {
+            keep_existing_vars(&mut env_vars, &inherited);
+        }
+        env_vars.extend(args.extra_env.iter().cloned());
+
+        let command_args = if args.template_args {
+            substitute_args(resolver, &args.command, &mut values)?
+        } else {
+            args.command.clone()
+        };
+        let stdin = match &args.stdin {
+            Some(alias) => {
+                let value = secret_bytes(resolver, alias)?;
+                values.push((alias.clone(), value.clone()));
+                Some(value)
+            }
+            None => None,
+        };
+
+        let mut rendered = Vec::new();
+        for (template, output) in parse_renders(&args.render)? {
+            let replace = args.render_overwrite
+                || previous
+                    .iter()
+                    .any(|file| file.path() == Some(output.as_path()));
+            rendered.push(RenderedFile::write(
+                &template,
+                &output,
+                replace,
+                resolver,
+                &mut values,
+            )?);
+        }
+
+        let (command, cmd_args) = split_command_args(command_args)?;
+
+        Ok(Self {
+            cmd: build_command(&command, &cmd_args, inherited, env_vars),
+            stdin,
+            pipes,
+            values,
+            secret_dir,
+            rendered,
+        })
+    }
+
+    #[cfg(unix)]
+    fn can_exec(&self, redact: bool) -> bool {
+        self.secret_dir.is_none()
+            && self.rendered.is_empty()
+            && !redact
+            && self.stdin.is_none()
+            && self.pipes.is_empty()
+    }
+
+    // Starts the command with its stdin and `--as-file` pipes fed with
+    // secrets and, when redacting, its stdout and stderr copied to obscura's
+    // own with secret values masked.
+    fn start(&mut self, redact: bool) -> ObscuraResult<Started> {
+        let stdin = self.stdin.take();
+        if stdin.is_some() {
+            self.cmd.stdin(Stdio::piped());
+        }
+        if redact {
+            self.cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+            // Output to a pipe is block-buffered by most runtimes; ask the
+            // ones that have a switch for it to keep writing line by line.
+            for (name, value) in UNBUFFERED_HINTS {
+                if !self.cmd.get_envs().any(|(key, _)| key == *name) {
+                    self.cmd.env(name, value);
+                }
+            }
+        }
+        let mut running = spawn_forwarding_signals(&mut self.cmd)?;
+        for pipe in self.pipes.drain(..) {
+            pipe.feed();
+        }
+
+        if let (Some(mut input), Some(value)) = (running.child.stdin.take(), stdin) {
+            // The pipe is closed afterwards so the command sees end of input.
+            // A command that stops reading early is not an error.
+            thread::spawn(move || input.write_all(&value));
+        }
+
+        // A failed write drops the pipe, so the child sees a closed stream
+        // just as it would without redaction.
+        let mut pumps = Vec::new();
+        if redact {
+            let redactor = Arc::new(Redactor::new(&self.values));
+            if let Some(stdout) = running.child.stdout.take() {
+                let redactor = Arc::clone(&redactor);
+                pumps.push(thread::spawn(move || redactor.pump(stdout, io::stdout())));
+            }
+            if let Some(stderr) = running.child.stderr.take() {
+                pumps.push(thread::spawn(move || redactor.pump(stderr, io::stderr())));
+            }
+        }
+
+        Ok(Started { running, pumps })
+    }
+}
+
+struct Started {
+    running: RunningChild,
+    pumps: Vec<JoinHandle<io::Result<()>>>,
+}
+
+impl Started {
+    fn wait(self) -> ObscuraResult<ExitStatus> {
+        let status = self.running.wait();
+        join_pumps(self.pumps);
+        status
+    }
+
+    fn stop(self, signal: i32) -> ObscuraResult<ExitStatus> {
+        let status = self.running.stop(signal, STOP_TIMEOUT);
+        join_pumps(self.pumps);
+        status
+    }
+}
+
+// The rest of the output is copied before obscura prints or exits.
+fn join_pumps(pumps: Vec<JoinHandle<io::Result<()>>>) {
+    for pump in pumps {
+        let _ = pump.join();
+    }
+}
+
+// Restarts the command whenever one of the vaults it reads changes. Returns
+// only on error; when the command exits by itself, obscura exits with it.
+fn watch(
+    args: &RunArgs,
+    selection: &Selection,
+    mut resolver: SecretResolver,
+    mut launch: Launch,
+) -> ObscuraResult<()> {
+    let stop_signal = parse_signal(&args.stop_signal)?;
+    let debounce = Duration::from_millis(args.debounce);
+    let mut watcher = FileWatcher::new(resolver.vault_paths());
+    let mut started = launch.start(args.redact)?;
+    // The command owns the terminal now; a passphrase prompt would fight it
+    // for input.
+    resolver.never_prompt();
+
+    loop {
+        if let Some(status) = started.running.try_wait()? {
+            let status = started.wait().unwrap_or(status);
+            drop(launch);
+            exit_like(status);
+        }
+        if !watcher.wait(WATCH_INTERVAL) {
+            continue;
+        }
+        watcher.settle(debounce);
+
+        // A vault that cannot be read or lacks a required secret leaves the
+        // running command alone until the next change.
+        let next = resolver
+            .reload()
+            .and_then(|_| Launch::prepare(args, selection, &mut resolver, &launch.rendered));
+        // Generating HOTP codes writes the vault; that is not a change.
+        watcher.reset();
+        let mut next = match next {
+            Ok(next) => next,
+            Err(err) => {
+                eprintln!("Vault changed but the command was not restarted: {}", err);
+                continue;
+            }
+        };
+
+        eprintln!("Vault changed, restarting '{}'", args.command.join(" "));
+        started.stop(stop_signal)?;
+        started = next.start(args.redact)?;
+        // The new render already replaced these files; they must outlive the old launch.
+        for file in std::mem::take(&mut launch.rendered) {
+            if next.rendered.iter().any(|new| new.path() == file.path()) {
+                file.keep();
+            }
+        }
+        launch = next;
+    }
+}
+
+// Which aliases `run` injects and under which names: the aliases declared
+// in the manifest when there is one, otherwise every top-level alias (or
+// every alias in the namespace). `--only`/`--exclude` patterns match names
+// relative to the namespace; `--map` entries are injected as given,
+// bypassing the filters and the prefix; `--as-file` aliases are not put in
+// the environment at all.
+struct Selection {
+    manifest: Option<Manifest>,
+    namespace: Option<String>,
+    only: Vec<String>,
+    exclude: Vec<String>,
+    map: Vec<(String, String)>,
+    as_file: Vec<(String, String)>,
+    prefix: String,
+}
+
+impl Selection {
+    fn open(args: &RunArgs) -> ObscuraResult<Self> {
+        let manifest = if args.no_manifest {
+            None
+        } else {
+            Manifest::find_current()?
+        };
+        if let (Some(manifest), Some(_)) = (&manifest, &args.namespace) {
+            return Err(ObscuraError::CustomError(format!(
+                "--namespace cannot be combined with {}; pass --no-manifest",
+                manifest.path.display()
+            )));
+        }
+        Self::new(args, manifest)
+    }
+
+    fn new(args: &RunArgs, manifest: Option<Manifest>) -> ObscuraResult<Self> {
+        Ok(Self {
+            manifest,
+            namespace: args.namespace.as_deref().map(normalize_namespace),
+            only: args.only.clone(),
+            exclude: args.exclude.clone(),
+            map: parse_pairs("--map", "ENV=ALIAS", &args.map, '=')?,
+            as_file: parse_pairs("--as-file", "ALIAS=ENVVAR", &args.as_file, '=')?,
+            prefix: args.prefix.clone().unwrap_or_default(),
+        })
+    }
+
+    // Without a namespace only top-level aliases are injected, since nested
+    // names are not valid environment variable names.
+    fn relative_name<'a>(&self, alias: &'a str) -> Option<&'a str> {
+        match &self.namespace {
+            Some(namespace) if is_in_namespace(alias, namespace) => alias.strip_prefix(namespace),
+            Some(_) => None,
+            None if is_namespaced(alias) => None,
+            None => Some(alias),
+        }
+    }
 
-    if !vault_exists(&vault_info.path) {
-        return Err(ObscuraError::VaultNotFound);
+    // Aliases named by `--map` or `--as-file` are only passed the way given there.
+    fn is_explicit(&self, alias: &str) -> bool {
+        self.map.iter().any(|(_, mapped)| mapped == alias)
+            || self
+                .as_file
+                .iter()
+                .any(|(file_alias, _)| file_alias == alias)
     }
 
-    let env_vars = get_secrets_as_env_vars(&vault_info.path)?;
+    fn includes(&self, name: &str) -> bool {
+        (self.only.is_empty() || self.only.iter().any(|pattern| glob_match(pattern, name)))
+            && !self.exclude.iter().any(|pattern| glob_match(pattern, name))
+    }
 
-    let (command, cmd_args) = split_command_args(args.command)?;
+    fn select(&self, entries: Vec<(String, usize)>) -> ObscuraResult<Vec<(String, String, usize)>> {
+        let mut selected = Vec::new();
+        let mut available = Vec::new();
+        let mut nested = Vec::new();
+        for (alias, index) in entries {
+            let relative = match self.relative_name(&alias) {
+                Some(relative) => relative.to_string(),
+                None => {
+                    if self.namespace.is_none()
+                        && !self.is_explicit(&alias)
+                        && self.includes(&alias)
+                    {
+                        nested.push(alias);
+                    }
+                    continue;
+                }
+            };
+            available.push(relative.clone());
+            if self.is_explicit(&alias) || !self.includes(&relative) {
+                continue;
+            }
+            let env_name = match &self.namespace {
+                Some(namespace) => env_name_in_namespace(&alias, namespace).unwrap_or(relative),
+                None => relative,
+            };
+            selected.push((format!("{}{}", self.prefix, env_name), alias, index));
+        }
 
-    let exit_code = execute_with_env_vars(&command, &cmd_args, env_vars)?;
+        if !nested.is_empty() {
+            eprintln!(
+                "Skipping {} namespaced alias(es) without --namespace or --map: {}",
+                nested.len(),
+                nested.join(", ")
+            );
+        }
+
+        let missing: Vec<&str> = self
+            .only
+            .iter()
+            .filter(|pattern| !has_wildcards(pattern) && !available.contains(pattern))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(ObscuraError::CustomError(format!(
+                "Aliases requested with --only not found: {}",
+                missing.join(", ")
+            )));
+        }
 
-    std::process::exit(exit_code);
+        Ok(selected)
+    }
+
+    fn select_manifest(
+        &self,
+        manifest: &Manifest,
+        resolver: &mut SecretResolver,
+    ) -> ObscuraResult<Vec<(String, String, usize)>> {
+        let mut selected = Vec::new();
+        let mut missing = Vec::new();
+        for (alias, spec) in &manifest.secrets {
+            if self.is_explicit(alias) || !self.includes(alias) {
+                continue;
+            }
+            match resolver.locate_in(spec.vault, alias)? {
+                Some(index) => selected.push((
+                    format!("{}{}", self.prefix, spec.env_name(alias)),
+                    alias.clone(),
+                    index,
+                )),
+                None if spec.required => missing.push(alias.as_str()),
+                None => {}
+            }
+        }
+
+        if !missing.is_empty() {
+            return Err(ObscuraError::CustomError(format!(
+                "Required secrets from {} are missing: {}",
+                manifest.path.display(),
+                missing.join(", ")
+            )));
+        }
+        Ok(selected)
+    }
 }
 
-fn get_secrets_as_env_vars(vault_path: &Path) -> ObscuraResult<HashMap<String, String>> {
-    let (dek, aliases_data) = load_aliases(vault_path)?;
+fn parse_pairs(
+    flag: &str,
+    format: &str,
+    entries: &[String],
+    separator: char,
+) -> ObscuraResult<Vec<(String, String)>> {
+    let mut pairs = Vec::new();
+    for entry in entries {
+        match entry.split_once(separator) {
+            Some((left, right)) if !left.is_empty() && !right.is_empty() => {
+                pairs.push((left.to_string(), right.to_string()))
+            }
+            _ => {
+                return Err(ObscuraError::CustomError(format!(
+                    "Invalid {} '{}': expected {}",
+                    flag, entry, format
+                )))
+            }
+        }
+    }
+    Ok(pairs)
+}
 
-    let aliases = aliases_data.list_aliases();
+fn parse_renders(entries: &[String]) -> ObscuraResult<Vec<(PathBuf, PathBuf)>> {
+    let pairs = parse_pairs("--render", "TEMPLATE:OUT", entries, ':')?;
+    Ok(pairs
+        .into_iter()
+        .map(|(template, output)| (PathBuf::from(template), PathBuf::from(output)))
+        .collect())
+}
+
+// The variables `run` would set for `args`, for printing instead of running
+// a command. File secrets are skipped since their files would not outlive
+// obscura.
+pub fn env_values(args: &RunArgs) -> ObscuraResult<Vec<(String, String)>> {
+    let selection = Selection::open(args)?;
+    let mut resolver = SecretResolver::open(args.layered, args.global, args.project)?;
+
+    let mut env_values = Vec::new();
+    for (env_name, alias, index) in selected_aliases(&mut resolver, &selection)? {
+        let vault = resolver.layer(index)?;
+        match vault.aliases.alias_kind(&alias) {
+            Some(AliasKind::File) => eprintln!(
+                "Skipping '{}' - file secrets can only be passed by 'obscura run'",
+                alias
+            ),
+            Some(AliasKind::Otp) => env_values.push((env_name, next_code(vault, &alias)?.code)),
+            _ => env_values.push((env_name, resolver.resolve_at(index, &alias)?)),
+        }
+    }
+    env_values.sort();
+    Ok(env_values)
+}
+
+// The names `env_values` would return, without decrypting any value, so
+// listing them does not advance HOTP counters.
+pub fn env_names(args: &RunArgs) -> ObscuraResult<Vec<String>> {
+    let selection = Selection::open(args)?;
+    let mut resolver = SecretResolver::open(args.layered, args.global, args.project)?;
+
+    let mut names = Vec::new();
+    for (env_name, alias, index) in selected_aliases(&mut resolver, &selection)? {
+        if resolver.layer(index)?.aliases.alias_kind(&alias) != Some(AliasKind::File) {
+            names.push(env_name);
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+// Environment name, alias and layer of every alias the selection injects.
+fn selected_aliases(
+    resolver: &mut SecretResolver,
+    selection: &Selection,
+) -> ObscuraResult<Vec<(String, String, usize)>> {
+    let mut aliases = match &selection.manifest {
+        Some(manifest) => selection.select_manifest(manifest, resolver)?,
+        None => selection.select(resolver.entries()?)?,
+    };
+    for (env_name, alias) in &selection.map {
+        let index = resolver.locate(alias)?.ok_or_else(|| {
+            ObscuraError::CustomError(format!("Alias '{}' in --map not found", alias))
+        })?;
+        aliases.push((env_name.clone(), alias.clone(), index));
+    }
+    Ok(aliases)
+}
+
+fn get_secrets_as_env_vars(
+    resolver: &mut SecretResolver,
+    selection: &Selection,
+    secret_dir: &mut Option<PrivateTempDir>,
+    values: &mut Vec<(String, Vec<u8>)>,
+) -> ObscuraResult<HashMap<String, String>> {
+    let aliases = selected_aliases(resolver, selection)?;
     let mut env_vars = HashMap::new();
 
-    for alias in &aliases {
-        if let Some(value) = aliases_data.get_alias(alias, &dek)? {
-            env_vars.insert(alias.clone(), value);
+    for (env_name, alias, index) in &aliases {
+        let kind = resolver.layer(*index)?.aliases.alias_kind(alias);
+        let value = match resolver.value_bytes_at(*index, alias)? {
+            Some(value) => value,
+            None => continue,
+        };
+        if kind == Some(AliasKind::File) {
+            if secret_dir.is_none() {
+                *secret_dir = Some(PrivateTempDir::create()?);
+            }
+            if let Some(dir) = secret_dir.as_ref() {
+                let path = dir.write_file(alias, &value)?;
+                env_vars.insert(env_name.clone(), path.to_string_lossy().to_string());
+            }
+        } else {
+            env_vars.insert(
+                env_name.clone(),
+                String::from_utf8_lossy(&value).into_owned(),
+            );
         }
+        values.push((alias.clone(), value));
     }
 
     Ok(env_vars)
 }
 
+fn secret_bytes(resolver: &mut SecretResolver, alias: &str) -> ObscuraResult<Vec<u8>> {
+    resolver
+        .value_bytes(alias)?
+        .ok_or_else(|| ObscuraError::AliasNotFound(alias.to_string()))
+}
+
+// Replaces `{{ALIAS}}` placeholders in the command and its arguments. Each
+// alias is looked up once, so an OTP code is the same in every argument.
+fn substitute_args(
+    resolver: &mut SecretResolver,
+    command_args: &[String],
+    values: &mut Vec<(String, Vec<u8>)>,
+) -> ObscuraResult<Vec<String>> {
+    let mut substituted: HashMap<String, Option<Vec<u8>>> = HashMap::new();
+    let mut result = Vec::with_capacity(command_args.len());
+
+    for arg in command_args {
+        let in_argument =
+            |err: ObscuraError| ObscuraError::CustomError(format!("Argument '{}': {}", arg, err));
+        let template = Template::parse(arg).map_err(in_argument)?;
+        let rendered = template
+            .render(|alias| {
+                if !substituted.contains_key(alias) {
+                    let value = resolver.value_bytes(alias)?;
+                    if let Some(value) = &value {
+                        values.push((alias.to_string(), value.clone()));
+                    }
+                    substituted.insert(alias.to_string(), value);
+                }
+                Ok(substituted[alias].clone())
+            })
+            .map_err(in_argument)?;
+        result.push(String::from_utf8(rendered).map_err(|_| {
+            ObscuraError::CustomError(format!(
+                "Argument '{}' is not valid UTF-8 once its secrets are substituted",
+                arg
+            ))
+        })?);
+    }
+
+    if !substituted.is_empty() {
+        eprintln!(
+            "Warning: secrets in command arguments are visible to other processes of this user (ps, /proc/<pid>/cmdline)"
+        );
+    }
+    Ok(result)
+}
+
+// Only variables the child would actually inherit count as existing, so
+// --clean-env does not keep a secret out because the parent happens to set it.
+fn keep_existing_vars(env_vars: &mut HashMap<String, String>, inherited: &[(OsString, OsString)]) {
+    let mut kept: Vec<String> = env_vars
+        .keys()
+        .filter(|name| {
+            inherited
+                .iter()
+                .any(|(existing, _)| same_var_name(&existing.to_string_lossy(), name))
+        })
+        .cloned()
+        .collect();
+    kept.sort();
+    for name in &kept {
+        env_vars.remove(name);
+    }
+    if !kept.is_empty() {
+        eprintln!("Keeping existing variables: {}", kept.join(", "));
+    }
+}
+
+// The parent's variables the child starts from. Obscura's own variables,
+// such as OBSCURA_PASSPHRASE, are never passed on unless kept explicitly.
+fn inherited_vars(clean_env: bool, keep: &[String]) -> Vec<(OsString, OsString)> {
+    std::env::vars_os()
+        .filter(|(name, _)| {
+            let name = name.to_string_lossy();
+            if keep.iter().any(|pattern| glob_match(pattern, &name)) {
+                true
+            } else if clean_env {
+                CLEAN_ENV_VARS
+                    .iter()
+                    .chain(CLEAN_ENV_PLATFORM_VARS)
+                    .any(|allowed| same_var_name(&name, allowed))
+            } else {
+                !name.starts_with(OBSCURA_VAR_PREFIX)
+            }
+        })
+        .collect()
+}
+
+// Variable names are case-insensitive on Windows only.
+fn same_var_name(a: &str, b: &str) -> bool {
+    if cfg!(windows) {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
 fn split_command_args(command_args: Vec<String>) -> ObscuraResult<(String, Vec<String>)> {
     if command_args.is_empty() {
         return Err(ObscuraError::InvalidVaultFormat);
@@ -65,22 +833,24 @@ fn split_command_args(command_args: Vec<String>) -> ObscuraResult<(String, Vec<S
     Ok((command, args))
 }
 
-fn execute_with_env_vars(
+fn build_command(
     command: &str,
     args: &[String],
+    inherited: Vec<(OsString, OsString)>,
     env_vars: HashMap<String, String>,
-) -> ObscuraResult<i32> {
+) -> Command {
     let mut cmd = Command::new(command);
 
     for arg in args {
         cmd.arg(arg);
     }
 
+    cmd.env_clear();
+    cmd.envs(inherited);
+
     for (key, value) in env_vars {
         cmd.env(key, value);
     }
 
-    let mut child = cmd.spawn()?;
-    let status = child.wait()?;
-    Ok(status.code().unwrap_or(1))
+    cmd
 }
@@ -0,0 +1,261 @@
+use crate::cmd::common::{vault_scope, LoadedVault, VaultScope};
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::pattern::{glob_match, has_wildcards};
+use crate::util::time::{parse_timestamp, short_date};
+use crate::vault::file::vault_exists;
+use crate::vault::model::{AliasData, AliasKind};
+use chrono::{DateTime, NaiveDate, Utc};
+use clap::Args;
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+
+#[derive(Args)]
+pub struct SearchArgs {
+    #[arg(
+        help = "Glob pattern for alias names; text without wildcards matches anywhere in the name"
+    )]
+    pub pattern: Option<String>,
+
+    #[arg(long, short = 'g', help = "Search only the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Search only the project vault")]
+    pub project: bool,
+
+    #[arg(long, short = 'e', help = "Treat the pattern as a regular expression")]
+    pub regex: bool,
+
+    #[arg(long, short = 'i', help = "Match alias names case-insensitively")]
+    pub ignore_case: bool,
+
+    #[arg(
+        long = "tag",
+        value_name = "TAG",
+        value_delimiter = ',',
+        help = "Only aliases carrying this tag (repeatable, all must match)"
+    )]
+    pub tags: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "Created on or after this date (YYYY-MM-DD or RFC 3339)"
+    )]
+    pub created_after: Option<String>,
+
+    #[arg(long, value_name = "DATE", help = "Created before this date")]
+    pub created_before: Option<String>,
+
+    #[arg(long, value_name = "DATE", help = "Rotated on or after this date")]
+    pub rotated_after: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DATE",
+        help = "Rotated before this date, or never rotated"
+    )]
+    pub rotated_before: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["rotated_after", "rotated_before"],
+        help = "Only aliases that have never been rotated"
+    )]
+    pub never_rotated: bool,
+
+    #[arg(long, help = "Render output as JSON")]
+    pub json: bool,
+}
+
+#[derive(Serialize)]
+struct SearchHit {
+    alias: String,
+    vault: &'static str,
+    kind: AliasKind,
+    created_at: String,
+    rotated_at: Option<String>,
+    tags: Vec<String>,
+}
+
+enum NameMatcher {
+    Any,
+    Regex(Regex),
+    Glob(String),
+    Substring(String),
+}
+
+impl NameMatcher {
+    fn new(pattern: Option<&str>, regex: bool, ignore_case: bool) -> ObscuraResult<Self> {
+        let pattern = match pattern {
+            Some(pattern) => pattern,
+            None => return Ok(NameMatcher::Any),
+        };
+        if regex {
+            return RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map(NameMatcher::Regex)
+                .map_err(|err| ObscuraError::CustomError(format!("Invalid regex: {}", err)));
+        }
+        let pattern = if ignore_case {
+            pattern.to_lowercase()
+        } else {
+            pattern.to_string()
+        };
+        if has_wildcards(&pattern) {
+            Ok(NameMatcher::Glob(pattern))
+        } else {
+            Ok(NameMatcher::Substring(pattern))
+        }
+    }
+
+    fn matches(&self, alias: &str, ignore_case: bool) -> bool {
+        let alias = if ignore_case {
+            alias.to_lowercase()
+        } else {
+            alias.to_string()
+        };
+        match self {
+            NameMatcher::Any => true,
+            NameMatcher::Regex(regex) => regex.is_match(&alias),
+            NameMatcher::Glob(pattern) => glob_match(pattern, &alias),
+            NameMatcher::Substring(text) => alias.contains(text.as_str()),
+        }
+    }
+}
+
+struct DateFilters {
+    created_after: Option<DateTime<Utc>>,
+    created_before: Option<DateTime<Utc>>,
+    rotated_after: Option<DateTime<Utc>>,
+    rotated_before: Option<DateTime<Utc>>,
+    never_rotated: bool,
+}
+
+impl DateFilters {
+    fn matches(&self, alias_data: &AliasData) -> bool {
+        let created = parse_timestamp(&alias_data.created_at);
+        let rotated = alias_data.rotated_at.as_deref().and_then(parse_timestamp);
+
+        if self.never_rotated && alias_data.rotated_at.is_some() {
+            return false;
+        }
+        if let Some(after) = self.created_after {
+            if created.is_none_or(|created| created < after) {
+                return false;
+            }
+        }
+        if let Some(before) = self.created_before {
+            if created.is_none_or(|created| created >= before) {
+                return false;
+            }
+        }
+        if let Some(after) = self.rotated_after {
+            if rotated.is_none_or(|rotated| rotated < after) {
+                return false;
+            }
+        }
+        if let Some(before) = self.rotated_before {
+            if rotated.is_some_and(|rotated| rotated >= before) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+pub fn handle_search(args: SearchArgs) -> ObscuraResult<()> {
+    let matcher = NameMatcher::new(args.pattern.as_deref(), args.regex, args.ignore_case)?;
+    let filters = DateFilters {
+        created_after: args.created_after.as_deref().map(parse_date).transpose()?,
+        created_before: args.created_before.as_deref().map(parse_date).transpose()?,
+        rotated_after: args.rotated_after.as_deref().map(parse_date).transpose()?,
+        rotated_before: args.rotated_before.as_deref().map(parse_date).transpose()?,
+        never_rotated: args.never_rotated,
+    };
+
+    let scopes = match (args.global, args.project) {
+        (true, false) => vec![VaultScope::Global],
+        (false, true) => vec![VaultScope::Project],
+        _ => vec![VaultScope::Project, VaultScope::Global],
+    };
+
+    let mut hits = Vec::new();
+    let mut searched = 0;
+    for scope in scopes {
+        let vault_info = match scope.resolve() {
+            Ok(vault_info) if vault_exists(&vault_info.path) => vault_info,
+            _ => continue,
+        };
+        // Only the vault body is decrypted; alias values stay encrypted.
+        let vault = LoadedVault::load(vault_info)?;
+        searched += 1;
+
+        for (alias, alias_data) in &vault.aliases.aliases {
+            if !matcher.matches(alias, args.ignore_case)
+                || !filters.matches(alias_data)
+                || !args.tags.iter().all(|tag| alias_data.tags.contains(tag))
+            {
+                continue;
+            }
+            hits.push(SearchHit {
+                alias: alias.clone(),
+                vault: vault_scope(vault.info.vault_type),
+                kind: alias_data.kind,
+                created_at: alias_data.created_at.clone(),
+                rotated_at: alias_data.rotated_at.clone(),
+                tags: alias_data.tags.clone(),
+            });
+        }
+    }
+
+    if searched == 0 {
+        return Err(ObscuraError::VaultNotFound);
+    }
+
+    hits.sort_by(|a, b| a.alias.cmp(&b.alias).then(b.vault.cmp(a.vault)));
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&hits)?);
+        return Ok(());
+    }
+
+    if hits.is_empty() {
+        println!("No matching aliases");
+        return Ok(());
+    }
+
+    let width = hits.iter().map(|hit| hit.alias.len()).max().unwrap_or(0);
+    for hit in &hits {
+        println!(
+            "{:<7}  {:<width$}  created {}  rotated {}{}",
+            hit.vault,
+            hit.alias,
+            short_date(&hit.created_at),
+            hit.rotated_at.as_deref().map(short_date).unwrap_or("never"),
+            if hit.tags.is_empty() {
+                String::new()
+            } else {
+                format!("  [{}]", hit.tags.join(", "))
+            },
+            width = width
+        );
+    }
+    Ok(())
+}
+
+fn parse_date(value: &str) -> ObscuraResult<DateTime<Utc>> {
+    if let Some(timestamp) = parse_timestamp(value) {
+        return Ok(timestamp);
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc())
+        .ok_or_else(|| {
+            ObscuraError::CustomError(format!(
+                "Invalid date '{}' (expected YYYY-MM-DD or RFC 3339)",
+                value
+            ))
+        })
+}
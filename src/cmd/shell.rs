@@ -0,0 +1,104 @@
+use crate::cmd::common::vault_scope;
+use crate::cmd::run::{handle_run, RunArgs};
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::manager::VaultManager;
+use clap::Args;
+
+// Set in `obscura shell` for prompts to show, e.g. PS1='($OBSCURA_SHELL) '$PS1.
+pub const SHELL_MARKER: &str = "OBSCURA_SHELL";
+
+#[derive(Args)]
+pub struct ShellArgs {
+    #[arg(long, short = 'g', help = "Use the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Use the project vault")]
+    pub project: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["global", "project"],
+        help = "Use project vaults merged over the global vault"
+    )]
+    pub layered: bool,
+
+    #[arg(
+        long,
+        short = 'n',
+        value_name = "NAMESPACE",
+        help = "Inject only aliases under this namespace, named by their leaf names"
+    )]
+    pub namespace: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_delimiter = ',',
+        help = "Inject only matching aliases (comma-separated names or globs)"
+    )]
+    pub only: Vec<String>,
+
+    #[arg(
+        long,
+        value_name = "PATTERN",
+        value_delimiter = ',',
+        help = "Do not inject matching aliases (comma-separated names or globs)"
+    )]
+    pub exclude: Vec<String>,
+
+    #[arg(long, help = "Ignore obscura.toml and inject every alias")]
+    pub no_manifest: bool,
+
+    #[arg(long, help = "Skip the confirmation for protected environments")]
+    pub yes: bool,
+}
+
+// Starts the user's shell the way `run` starts a command.
+pub fn handle_shell(args: ShellArgs) -> ObscuraResult<()> {
+    if let Some(current) = std::env::var_os(SHELL_MARKER) {
+        return Err(ObscuraError::CustomError(format!(
+            "Already in an obscura shell ({}); type 'exit' to leave it first",
+            current.to_string_lossy()
+        )));
+    }
+
+    let label = if args.layered {
+        "layered".to_string()
+    } else {
+        let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
+        match &vault_info.environment {
+            Some(environment) => format!("{}:{}", vault_scope(vault_info.vault_type), environment),
+            None => vault_scope(vault_info.vault_type).to_string(),
+        }
+    };
+    let shell = user_shell();
+    eprintln!(
+        "Starting {} with secrets from the {} vault; type 'exit' to leave",
+        shell, label
+    );
+
+    handle_run(RunArgs {
+        global: args.global,
+        project: args.project,
+        layered: args.layered,
+        namespace: args.namespace,
+        only: args.only,
+        exclude: args.exclude,
+        no_manifest: args.no_manifest,
+        yes: args.yes,
+        command: vec![shell],
+        extra_env: vec![(SHELL_MARKER.to_string(), label)],
+        ..Default::default()
+    })
+}
+
+fn user_shell() -> String {
+    #[cfg(windows)]
+    let (variable, fallback) = ("COMSPEC", "cmd.exe");
+    #[cfg(not(windows))]
+    let (variable, fallback) = ("SHELL", "/bin/sh");
+    std::env::var(variable)
+        .ok()
+        .filter(|shell| !shell.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
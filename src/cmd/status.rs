@@ -0,0 +1,109 @@
+use crate::cmd::common::vault_scope;
+use crate::session::SessionStore;
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::file::vault_exists;
+use crate::vault::identity::project_identity;
+use crate::vault::manager::{
+    parent_search_enabled, VaultInfo, VaultManager, VaultType, NO_PARENT_SEARCH_ENV,
+};
+use clap::Args;
+use serde_json::{json, to_string_pretty};
+
+#[derive(Args)]
+pub struct StatusArgs {
+    #[arg(long, short = 'g', help = "Show the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Show the project vault")]
+    pub project: bool,
+
+    #[arg(long, help = "Render output as JSON")]
+    pub json: bool,
+}
+
+pub fn handle_status(args: StatusArgs) -> ObscuraResult<()> {
+    let current_dir = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+    let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
+    let exists = vault_exists(&vault_info.path);
+    let unlocked = exists && SessionStore::fetch_dek(&vault_info.path)?.is_some();
+    let global_exists = vault_exists(&VaultInfo::global()?.path);
+
+    let state = match (exists, unlocked) {
+        (false, _) => "not initialized",
+        (true, true) => "unlocked",
+        (true, false) => "locked",
+    };
+    let project_dir = match vault_info.vault_type {
+        VaultType::Project => vault_info.project_dir.as_ref(),
+        VaultType::Global => None,
+    };
+    let identity = project_dir.and_then(|dir| match project_identity(dir) {
+        Ok(identity) => Some(identity.to_string()),
+        Err(err) => {
+            eprintln!("Warning: {}", err);
+            None
+        }
+    });
+
+    if args.json {
+        println!(
+            "{}",
+            to_string_pretty(&json!({
+                "vault_type": vault_scope(vault_info.vault_type),
+                "vault_path": vault_info.path,
+                "in_repo": vault_info.in_repo,
+                "project_dir": project_dir,
+                "identity": identity,
+                "environment": vault_info.environment,
+                "protected": VaultManager::is_protected(&vault_info),
+                "current_dir": current_dir,
+                "state": state,
+                "global_vault_exists": global_exists,
+                "parent_search": parent_search_enabled(),
+            }))?
+        );
+        return Ok(());
+    }
+
+    println!("Vault:         {}", vault_scope(vault_info.vault_type));
+    if let Some(project_dir) = project_dir {
+        if *project_dir == current_dir {
+            println!("Project:       {}", project_dir.display());
+        } else {
+            println!(
+                "Project:       {} (found from {})",
+                project_dir.display(),
+                current_dir.display()
+            );
+        }
+    }
+    if let Some(identity) = &identity {
+        println!("Identity:      {}", identity);
+    }
+    if let Some(environment) = &vault_info.environment {
+        if VaultManager::is_protected(&vault_info) {
+            println!("Environment:   {} (protected)", environment);
+        } else {
+            println!("Environment:   {}", environment);
+        }
+    }
+    if vault_info.in_repo {
+        println!("Path:          {} (in repo)", vault_info.path.display());
+    } else {
+        println!("Path:          {}", vault_info.path.display());
+    }
+    println!("State:         {}", state);
+    println!(
+        "Global vault:  {}",
+        if global_exists { "present" } else { "missing" }
+    );
+    if parent_search_enabled() {
+        println!("Parent search: enabled");
+    } else {
+        println!("Parent search: disabled ({})", NO_PARENT_SEARCH_ENV);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use crate::cmd::common::{vault_scope, LoadedVault};
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::manager::VaultManager;
+use clap::Args;
+
+#[derive(Args)]
+pub struct TagArgs {
+    #[arg(help = "Alias to tag")]
+    pub alias: String,
+
+    #[arg(
+        required = true,
+        value_delimiter = ',',
+        help = "Tags to add (or remove with --remove)"
+    )]
+    pub tags: Vec<String>,
+
+    #[arg(long, short = 'g', help = "Operate on the global vault")]
+    pub global: bool,
+
+    #[arg(long, short = 'p', help = "Operate on the project vault")]
+    pub project: bool,
+
+    #[arg(long, help = "Remove the tags instead of adding them")]
+    pub remove: bool,
+}
+
+pub fn handle_tag(args: TagArgs) -> ObscuraResult<()> {
+    let vault_info = VaultManager::resolve_vault(args.global, args.project)?;
+    let mut vault = LoadedVault::load(vault_info)?;
+
+    let found = if args.remove {
+        vault.aliases.remove_tags(&args.alias, &args.tags)
+    } else {
+        vault.aliases.set_tags(&args.alias, &args.tags)
+    };
+    if !found {
+        return Err(ObscuraError::AliasNotFound(args.alias));
+    }
+    vault.save()?;
+
+    let tags = &vault.aliases.aliases[&args.alias].tags;
+    println!(
+        "Tags for '{}' in {} vault: {}",
+        args.alias,
+        vault_scope(vault.info.vault_type),
+        if tags.is_empty() {
+            "(none)".to_string()
+        } else {
+            tags.join(", ")
+        }
+    );
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use crate::cmd::common::{vault_scope, LoadedVault, VaultScope};
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::pattern::{glob_base, glob_match, has_wildcards};
+use crate::vault::manager::VaultManager;
+use crate::vault::model::AliasesData;
+use crate::vault::namespace::{normalize_namespace, validate_alias, SEPARATOR};
+use clap::Args;
+
+#[derive(Args)]
+pub struct TransferArgs {
+    #[arg(help = "Alias to transfer; glob patterns such as 'aws/old/*' select several")]
+    pub source: String,
+
+    #[arg(help = "New alias name, or a namespace ending in '/' (defaults to the source name)")]
+    pub destination: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Vault to read from (defaults to the resolved vault)"
+    )]
+    pub from: Option<VaultScope>,
+
+    #[arg(
+        long,
+        value_enum,
+        help = "Vault to write to (defaults to the source vault)"
+    )]
+    pub to: Option<VaultScope>,
+
+    #[arg(long, help = "Replace aliases that already exist in the destination")]
+    pub overwrite: bool,
+}
+
+pub fn handle_mv(args: TransferArgs) -> ObscuraResult<()> {
+    transfer(args, true)
+}
+
+pub fn handle_cp(args: TransferArgs) -> ObscuraResult<()> {
+    transfer(args, false)
+}
+
+fn transfer(args: TransferArgs, remove_source: bool) -> ObscuraResult<()> {
+    let source_info = match args.from {
+        Some(scope) => scope.resolve()?,
+        None => VaultManager::resolve_vault(false, false)?,
+    };
+    let destination_info = match args.to {
+        Some(scope) => scope.resolve()?,
+        None => source_info.clone(),
+    };
+    let same_vault = source_info.path == destination_info.path;
+    let verb = if remove_source { "Moved" } else { "Copied" };
+
+    let mut source = LoadedVault::load(source_info)?;
+    let plan = plan_transfer(&source.aliases, &args.source, args.destination.as_deref())?;
+
+    if same_vault {
+        if plan.iter().any(|(from, to)| from == to) {
+            return Err(ObscuraError::CustomError(
+                "Source and destination are the same alias".to_string(),
+            ));
+        }
+        check_conflicts(&source.aliases, &plan, remove_source, args.overwrite)?;
+
+        let entries: Vec<_> = plan
+            .iter()
+            .map(|(from, to)| (to.clone(), source.aliases.aliases[from].clone()))
+            .collect();
+        if remove_source {
+            for (from, _) in &plan {
+                source.aliases.remove_alias(from);
+            }
+        }
+        source.aliases.aliases.extend(entries);
+        source.save()?;
+
+        for (from, to) in &plan {
+            println!("{} '{}' to '{}'", verb, from, to);
+        }
+        return Ok(());
+    }
+
+    let mut destination = LoadedVault::load(destination_info)?;
+    check_conflicts(&destination.aliases, &plan, false, args.overwrite)?;
+
+    for (from, to) in &plan {
+        let entry = source.aliases.aliases[from].reencrypt(&source.dek, &destination.dek)?;
+        destination.aliases.aliases.insert(to.clone(), entry);
+    }
+
+    // The destination is written first: if removing from the source fails the
+    // secrets exist twice, never zero times.
+    destination.save()?;
+
+    if remove_source {
+        for (from, _) in &plan {
+            source.aliases.remove_alias(from);
+        }
+        if let Err(err) = source.save() {
+            eprintln!(
+                "Copied {} aliases to the {} vault but could not remove them from the {} vault",
+                plan.len(),
+                vault_scope(destination.info.vault_type),
+                vault_scope(source.info.vault_type)
+            );
+            return Err(err);
+        }
+    }
+
+    for (from, to) in &plan {
+        println!(
+            "{} '{}' ({}) to '{}' ({})",
+            verb,
+            from,
+            vault_scope(source.info.vault_type),
+            to,
+            vault_scope(destination.info.vault_type)
+        );
+    }
+    Ok(())
+}
+
+fn plan_transfer(
+    aliases: &AliasesData,
+    source: &str,
+    destination: Option<&str>,
+) -> ObscuraResult<Vec<(String, String)>> {
+    let plan: Vec<(String, String)> = if has_wildcards(source) {
+        let base = glob_base(source);
+        let namespace = destination.map(normalize_namespace);
+        let plan: Vec<_> = aliases
+            .list_aliases()
+            .into_iter()
+            .filter(|alias| glob_match(source, alias))
+            .map(|alias| {
+                let target = match &namespace {
+                    Some(namespace) => format!("{}{}", namespace, &alias[base.len()..]),
+                    None => alias.clone(),
+                };
+                (alias, target)
+            })
+            .collect();
+        if plan.is_empty() {
+            return Err(ObscuraError::AliasNotFound(source.to_string()));
+        }
+        plan
+    } else {
+        if !aliases.aliases.contains_key(source) {
+            return Err(ObscuraError::AliasNotFound(source.to_string()));
+        }
+        let target = match destination {
+            None => source.to_string(),
+            Some(destination) if destination.ends_with(SEPARATOR) => {
+                let leaf = source.rsplit(SEPARATOR).next().unwrap_or(source);
+                format!("{}{}", normalize_namespace(destination), leaf)
+            }
+            Some(destination) => destination.to_string(),
+        };
+        vec![(source.to_string(), target)]
+    };
+
+    for (_, target) in &plan {
+        validate_alias(target)?;
+    }
+    Ok(plan)
+}
+
+fn check_conflicts(
+    destination: &AliasesData,
+    plan: &[(String, String)],
+    sources_removed: bool,
+    overwrite: bool,
+) -> ObscuraResult<()> {
+    if overwrite {
+        return Ok(());
+    }
+
+    let conflicts: Vec<&str> = plan
+        .iter()
+        .map(|(_, to)| to.as_str())
+        .filter(|to| destination.aliases.contains_key(*to))
+        .filter(|to| !(sources_removed && plan.iter().any(|(from, _)| from == to)))
+        .collect();
+
+    if conflicts.is_empty() {
+        Ok(())
+    } else {
+        Err(ObscuraError::CustomError(format!(
+            "Destination already contains {}; pass --overwrite to replace",
+            conflicts
+                .iter()
+                .map(|alias| format!("'{}'", alias))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )))
+    }
+}
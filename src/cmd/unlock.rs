@@ -35,6 +35,7 @@ pub fn handle_unlock(args: UnlockArgs) -> ObscuraResult<()> {
 
     let vault_file = read_vault_file(&vault_info.path)?;
     let (dek, _) = decrypt_vault(&vault_file, &passphrase)?;
+    VaultManager::touch_project(&vault_info.path);
 
     SessionStore::store_dek(&vault_info.path, &dek, args.timeout)?;
     let scope = match vault_info.vault_type {
@@ -38,13 +38,24 @@ pub struct AeadResult {
 
 impl AeadResult {
     pub fn encrypt(plaintext: &[u8], key: &AeadKey, aad: &[u8]) -> ObscuraResult<Self> {
-        let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
         let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
         let nonce_bytes = nonce.as_slice().try_into().unwrap();
+        Self::encrypt_with_nonce(plaintext, key, &nonce_bytes, aad)
+    }
+
+    // The caller guarantees that a nonce is never reused for different
+    // plaintexts, e.g. by deriving it from the plaintext with a keyed hash.
+    pub fn encrypt_with_nonce(
+        plaintext: &[u8],
+        key: &AeadKey,
+        nonce: &[u8; 24],
+        aad: &[u8],
+    ) -> ObscuraResult<Self> {
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_bytes()));
 
         let ciphertext = cipher
             .encrypt(
-                &nonce,
+                XNonce::from_slice(nonce),
                 Payload {
                     msg: plaintext,
                     aad,
@@ -53,7 +64,7 @@ impl AeadResult {
             .map_err(|_| ObscuraError::EncryptionFailed)?;
 
         Ok(Self {
-            nonce: nonce_bytes,
+            nonce: *nonce,
             ciphertext,
         })
     }
@@ -83,6 +94,15 @@ pub fn encrypt_with_key(plaintext: &[u8], key: &AeadKey, aad: &[u8]) -> ObscuraR
     AeadResult::encrypt(plaintext, key, aad)
 }
 
+pub fn encrypt_with_nonce(
+    plaintext: &[u8],
+    key: &AeadKey,
+    nonce: &[u8; 24],
+    aad: &[u8],
+) -> ObscuraResult<AeadResult> {
+    AeadResult::encrypt_with_nonce(plaintext, key, nonce, aad)
+}
+
 pub fn decrypt_with_key(
     ciphertext: &[u8],
     key: &AeadKey,
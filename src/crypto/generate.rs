@@ -0,0 +1,343 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use base64::{engine::general_purpose, Engine as _};
+use data_encoding::BASE32_NOPAD;
+use rand::{rngs::OsRng, Rng, RngCore};
+
+pub const DEFAULT_SPEC: &str = "chars:32";
+
+const DEFAULT_CHAR_LENGTH: usize = 32;
+const DEFAULT_BYTE_LENGTH: usize = 32;
+// The built-in list has 708 words (about 9.5 bits each), so nine words give
+// roughly 85 bits.
+const DEFAULT_WORD_COUNT: usize = 9;
+const MAX_LENGTH: usize = 4096;
+const MAX_ATTEMPTS: usize = 10_000;
+
+const LOWER: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPER: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+// Quotes, backslash, backtick and `$` are left out so values survive shells,
+// dotenv files and `${...}` references.
+const SYMBOLS: &str = "!#%&()*+,-./:;<=>?@[]^_{|}~";
+const AMBIGUOUS: &str = "0O1lI|";
+
+const WORDLIST: &str = include_str!("wordlist.txt");
+
+enum Mode {
+    Chars {
+        charset: Vec<char>,
+        required: Vec<Vec<char>>,
+    },
+    Hex,
+    Base64 {
+        url_safe: bool,
+    },
+    Base32,
+    Uuid,
+    Words {
+        separator: String,
+        capitalize: bool,
+    },
+}
+
+struct Spec {
+    mode: Mode,
+    length: usize,
+}
+
+// Spec grammar: `MODE[:LENGTH][,OPTION...]`, e.g. `chars:24,set=alnum+symbols,require=upper+digits`,
+// `hex:32`, `base64:48,url`, `base32:20`, `uuid` or `words:5,sep=.,capitalize`.
+// A backslash escapes `,`, `+` or itself in option values.
+pub fn generate_secret(spec: &str) -> ObscuraResult<String> {
+    let spec = parse_spec(spec)?;
+
+    match &spec.mode {
+        Mode::Chars { charset, required } => generate_chars(charset, required, spec.length),
+        Mode::Hex => Ok(hex::encode(random_bytes(spec.length))),
+        Mode::Base64 { url_safe } => {
+            let bytes = random_bytes(spec.length);
+            Ok(if *url_safe {
+                general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+            } else {
+                general_purpose::STANDARD.encode(bytes)
+            })
+        }
+        Mode::Base32 => Ok(BASE32_NOPAD.encode(&random_bytes(spec.length))),
+        Mode::Uuid => Ok(generate_uuid()),
+        Mode::Words {
+            separator,
+            capitalize,
+        } => Ok(generate_words(spec.length, separator, *capitalize)),
+    }
+}
+
+fn parse_spec(spec: &str) -> ObscuraResult<Spec> {
+    let parts = split_escaped(spec, ',');
+    let mut parts = parts.iter().map(|part| part.trim());
+    let head = parts
+        .next()
+        .filter(|head| !head.is_empty())
+        .unwrap_or("chars");
+
+    let (mode_name, length) = match head.split_once(':') {
+        Some((mode, length)) => (mode, Some(parse_length(length)?)),
+        None if head.chars().all(|c| c.is_ascii_digit()) => ("chars", Some(parse_length(head)?)),
+        None => (head, None),
+    };
+
+    let mut sets: Vec<String> = Vec::new();
+    let mut required: Vec<String> = Vec::new();
+    let mut exclude = String::new();
+    let mut no_ambiguous = false;
+    let mut url_safe = false;
+    let mut separator = "-".to_string();
+    let mut capitalize = false;
+
+    // Options that do not apply to the mode are errors rather than ignored,
+    // so a `require=` never silently goes unmet.
+    let allowed: &[&str] = match mode_name {
+        "chars" => &["set", "require", "exclude", "no-ambiguous"],
+        "base64" => &["url"],
+        "words" => &["sep", "capitalize"],
+        "hex" | "base32" | "uuid" => &[],
+        other => return Err(invalid_spec(&format!("unknown mode '{}'", other))),
+    };
+    if mode_name == "uuid" && length.is_some() {
+        return Err(invalid_spec("uuid does not take a length"));
+    }
+
+    for option in parts.filter(|option| !option.is_empty()) {
+        let (key, value) = match option.split_once('=') {
+            Some((key, value)) => (key.trim(), Some(value)),
+            None => (option, None),
+        };
+        match (key, value) {
+            ("set", Some(value)) => {
+                sets.extend(split_escaped(value, '+').iter().map(|set| unescape(set)))
+            }
+            ("require", Some(value)) => {
+                required.extend(split_escaped(value, '+').iter().map(|set| unescape(set)))
+            }
+            ("exclude", Some(value)) => exclude.push_str(&unescape(value)),
+            ("no-ambiguous", None) => no_ambiguous = true,
+            ("url", None) => url_safe = true,
+            ("sep", Some(value)) => separator = unescape(value),
+            ("capitalize", None) => capitalize = true,
+            _ => {
+                return Err(invalid_spec(&format!(
+                    "unknown option '{}'",
+                    unescape(option)
+                )))
+            }
+        }
+        if !allowed.contains(&key) {
+            return Err(invalid_spec(&format!(
+                "option '{}' does not apply to mode '{}'",
+                key, mode_name
+            )));
+        }
+    }
+
+    let mode = match mode_name {
+        "chars" => {
+            let mut removed: Vec<char> = exclude.chars().collect();
+            if no_ambiguous {
+                removed.extend(AMBIGUOUS.chars());
+            }
+            if sets.is_empty() {
+                sets.push("alnum".to_string());
+            }
+
+            let mut charset = Vec::new();
+            for set in &sets {
+                for c in charset_for(set)? {
+                    if !charset.contains(&c) && !removed.contains(&c) {
+                        charset.push(c);
+                    }
+                }
+            }
+            if charset.is_empty() {
+                return Err(invalid_spec("character set is empty"));
+            }
+
+            let mut required_sets = Vec::new();
+            for name in &required {
+                let class: Vec<char> = charset_for(name)?
+                    .into_iter()
+                    .filter(|c| charset.contains(c))
+                    .collect();
+                if class.is_empty() {
+                    return Err(invalid_spec(&format!(
+                        "required class '{}' is not part of the character set",
+                        name
+                    )));
+                }
+                required_sets.push(class);
+            }
+
+            Mode::Chars {
+                charset,
+                required: required_sets,
+            }
+        }
+        "hex" => Mode::Hex,
+        "base64" => Mode::Base64 { url_safe },
+        "base32" => Mode::Base32,
+        "uuid" => Mode::Uuid,
+        "words" => Mode::Words {
+            separator,
+            capitalize,
+        },
+        _ => unreachable!("modes are checked above"),
+    };
+
+    let length = length.unwrap_or(match mode {
+        Mode::Chars { .. } => DEFAULT_CHAR_LENGTH,
+        Mode::Words { .. } => DEFAULT_WORD_COUNT,
+        _ => DEFAULT_BYTE_LENGTH,
+    });
+
+    if let Mode::Chars { required, .. } = &mode {
+        if required.len() > length {
+            return Err(invalid_spec(
+                "length is shorter than the number of required classes",
+            ));
+        }
+    }
+
+    Ok(Spec { mode, length })
+}
+
+// Splits at `separator` unless it is escaped with a backslash. Escapes are
+// kept for the next level to split or `unescape`.
+fn split_escaped(text: &str, separator: char) -> Vec<String> {
+    let mut pieces = vec![String::new()];
+    let mut escaped = false;
+    for c in text.chars() {
+        let piece = pieces.last_mut().expect("pieces is never empty");
+        if escaped {
+            piece.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            piece.push(c);
+            escaped = true;
+        } else if c == separator {
+            pieces.push(String::new());
+        } else {
+            piece.push(c);
+        }
+    }
+    pieces
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+fn parse_length(value: &str) -> ObscuraResult<usize> {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|length| (1..=MAX_LENGTH).contains(length))
+        .ok_or_else(|| {
+            invalid_spec(&format!(
+                "length must be between 1 and {}, got '{}'",
+                MAX_LENGTH, value
+            ))
+        })
+}
+
+fn charset_for(name: &str) -> ObscuraResult<Vec<char>> {
+    let chars = match name.trim() {
+        "lower" => LOWER.to_string(),
+        "upper" => UPPER.to_string(),
+        "digit" | "digits" => DIGITS.to_string(),
+        "symbol" | "symbols" => SYMBOLS.to_string(),
+        "alpha" => format!("{}{}", LOWER, UPPER),
+        "alnum" => format!("{}{}{}", LOWER, UPPER, DIGITS),
+        "all" => format!("{}{}{}{}", LOWER, UPPER, DIGITS, SYMBOLS),
+        other => match other.strip_prefix("chars:") {
+            Some(custom) if !custom.is_empty() => custom.to_string(),
+            _ => {
+                return Err(invalid_spec(&format!(
+                    "unknown character class '{}'",
+                    other
+                )))
+            }
+        },
+    };
+    Ok(chars.chars().collect())
+}
+
+fn generate_chars(
+    charset: &[char],
+    required: &[Vec<char>],
+    length: usize,
+) -> ObscuraResult<String> {
+    // Rejection sampling keeps every accepted value uniformly distributed.
+    for _ in 0..MAX_ATTEMPTS {
+        let candidate: Vec<char> = (0..length)
+            .map(|_| charset[OsRng.gen_range(0..charset.len())])
+            .collect();
+        if required
+            .iter()
+            .all(|class| candidate.iter().any(|c| class.contains(c)))
+        {
+            return Ok(candidate.into_iter().collect());
+        }
+    }
+    Err(invalid_spec("could not satisfy the required classes"))
+}
+
+fn generate_words(count: usize, separator: &str, capitalize: bool) -> String {
+    let words: Vec<&str> = WORDLIST.lines().filter(|word| !word.is_empty()).collect();
+    (0..count)
+        .map(|_| {
+            let word = words[OsRng.gen_range(0..words.len())];
+            if capitalize {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn generate_uuid() -> String {
+    let mut bytes = random_bytes(16);
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+fn random_bytes(length: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; length];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+fn invalid_spec(reason: &str) -> ObscuraError {
+    ObscuraError::CustomError(format!("Invalid generator spec: {}", reason))
+}
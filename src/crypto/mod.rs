@@ -1,2 +1,4 @@
 pub mod aead;
+pub mod generate;
 pub mod kdf;
+pub mod otp;
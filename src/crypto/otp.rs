@@ -0,0 +1,178 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::model::{OtpAlgorithm, OtpMethod, OtpParams};
+use data_encoding::BASE32_NOPAD;
+use hmac::{Hmac, Mac};
+use percent_encoding::percent_decode_str;
+use sha1::Sha1;
+use sha2::{Sha256, Sha512};
+
+const DEFAULT_DIGITS: u32 = 6;
+const DEFAULT_PERIOD: u64 = 30;
+
+pub struct OtpSeed {
+    pub secret: String,
+    pub params: OtpParams,
+}
+
+// Accepts either an `otpauth://` URI or a bare base32 TOTP secret.
+pub fn parse_seed(input: &str) -> ObscuraResult<OtpSeed> {
+    let input = input.trim();
+    if input.starts_with("otpauth://") {
+        return parse_otpauth_uri(input);
+    }
+
+    let secret = normalize_secret(input);
+    decode_secret(&secret)?;
+    Ok(OtpSeed {
+        secret,
+        params: default_params(OtpMethod::Totp),
+    })
+}
+
+pub fn decode_secret(secret: &str) -> ObscuraResult<Vec<u8>> {
+    let secret = normalize_secret(secret);
+    let bytes = BASE32_NOPAD
+        .decode(secret.as_bytes())
+        .map_err(|_| ObscuraError::CustomError("OTP secret is not valid base32".to_string()))?;
+    if bytes.is_empty() {
+        return Err(ObscuraError::CustomError("OTP secret is empty".to_string()));
+    }
+    Ok(bytes)
+}
+
+pub fn hotp(secret: &[u8], counter: u64, params: &OtpParams) -> ObscuraResult<String> {
+    let digest = match params.algorithm {
+        OtpAlgorithm::Sha1 => hmac_digest::<Hmac<Sha1>>(secret, counter)?,
+        OtpAlgorithm::Sha256 => hmac_digest::<Hmac<Sha256>>(secret, counter)?,
+        OtpAlgorithm::Sha512 => hmac_digest::<Hmac<Sha512>>(secret, counter)?,
+    };
+
+    // Dynamic truncation from RFC 4226 section 5.3.
+    let offset = (digest[digest.len() - 1] & 0x0f) as usize;
+    let binary = ((digest[offset] as u32 & 0x7f) << 24)
+        | ((digest[offset + 1] as u32) << 16)
+        | ((digest[offset + 2] as u32) << 8)
+        | (digest[offset + 3] as u32);
+    let code = binary % 10u32.pow(params.digits);
+
+    Ok(format!("{:0width$}", code, width = params.digits as usize))
+}
+
+pub fn totp(secret: &[u8], unix_time: u64, params: &OtpParams) -> ObscuraResult<(String, u64)> {
+    let step = unix_time / params.period;
+    let remaining = params.period - unix_time % params.period;
+    Ok((hotp(secret, step, params)?, remaining))
+}
+
+fn hmac_digest<M: Mac + hmac::digest::KeyInit>(
+    secret: &[u8],
+    counter: u64,
+) -> ObscuraResult<Vec<u8>> {
+    let mut mac = <M as hmac::digest::KeyInit>::new_from_slice(secret)
+        .map_err(|_| ObscuraError::EncryptionFailed)?;
+    mac.update(&counter.to_be_bytes());
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+fn parse_otpauth_uri(uri: &str) -> ObscuraResult<OtpSeed> {
+    let invalid =
+        |reason: &str| ObscuraError::CustomError(format!("Invalid otpauth URI: {}", reason));
+
+    let rest = &uri["otpauth://".len()..];
+    let (method, rest) = rest
+        .split_once('/')
+        .ok_or_else(|| invalid("missing type"))?;
+    let method = match method.to_ascii_lowercase().as_str() {
+        "totp" => OtpMethod::Totp,
+        "hotp" => OtpMethod::Hotp,
+        other => return Err(invalid(&format!("unsupported type '{}'", other))),
+    };
+
+    let (label, query) = rest
+        .split_once('?')
+        .ok_or_else(|| invalid("missing secret"))?;
+    let label = decode_component(label);
+    let (label_issuer, account) = match label.split_once(':') {
+        Some((issuer, account)) => (Some(issuer.trim().to_string()), account.trim().to_string()),
+        None => (None, label.trim().to_string()),
+    };
+
+    let mut params = default_params(method);
+    params.issuer = label_issuer;
+    if !account.is_empty() {
+        params.account = Some(account);
+    }
+
+    let mut secret = None;
+    let mut has_counter = false;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = decode_component(value);
+        match key.to_ascii_lowercase().as_str() {
+            "secret" => secret = Some(normalize_secret(&value)),
+            "issuer" => params.issuer = Some(value),
+            "algorithm" => {
+                params.algorithm = match value.to_ascii_uppercase().as_str() {
+                    "SHA1" => OtpAlgorithm::Sha1,
+                    "SHA256" => OtpAlgorithm::Sha256,
+                    "SHA512" => OtpAlgorithm::Sha512,
+                    _ => return Err(invalid(&format!("unsupported algorithm '{}'", value))),
+                }
+            }
+            "digits" => {
+                params.digits = value
+                    .parse()
+                    .ok()
+                    .filter(|digits| (6..=8).contains(digits))
+                    .ok_or_else(|| invalid("digits must be between 6 and 8"))?
+            }
+            "period" => {
+                params.period = value
+                    .parse()
+                    .ok()
+                    .filter(|period| *period > 0)
+                    .ok_or_else(|| invalid("period must be a positive number"))?
+            }
+            "counter" => {
+                params.counter = value.parse().map_err(|_| invalid("invalid counter"))?;
+                has_counter = true;
+            }
+            _ => {}
+        }
+    }
+
+    if method == OtpMethod::Hotp && !has_counter {
+        return Err(invalid("HOTP URIs require a counter"));
+    }
+
+    let secret = secret.ok_or_else(|| invalid("missing secret"))?;
+    decode_secret(&secret)?;
+
+    Ok(OtpSeed { secret, params })
+}
+
+fn default_params(method: OtpMethod) -> OtpParams {
+    OtpParams {
+        method,
+        algorithm: OtpAlgorithm::Sha1,
+        digits: DEFAULT_DIGITS,
+        period: DEFAULT_PERIOD,
+        counter: 0,
+        issuer: None,
+        account: None,
+    }
+}
+
+fn normalize_secret(secret: &str) -> String {
+    secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-' && *c != '=')
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn decode_component(value: &str) -> String {
+    percent_decode_str(&value.replace('+', " "))
+        .decode_utf8_lossy()
+        .to_string()
+}
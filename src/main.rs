@@ -16,6 +16,15 @@ use cmd::*;
     version
 )]
 struct Cli {
+    #[arg(
+        long,
+        global = true,
+        env = "OBSCURA_ENV",
+        value_name = "NAME",
+        help = "Use a named environment of the project vault (e.g. staging, prod)"
+    )]
+    env: Option<String>,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -40,6 +49,27 @@ enum Commands {
     #[command(about = "List secrets in the vault (alias for 'list')")]
     Ls(list::ListArgs),
 
+    #[command(about = "Show which vault the current directory resolves to")]
+    Status(status::StatusArgs),
+
+    #[command(
+        visible_alias = "project",
+        about = "List, inspect, relocate, prune and link project vaults"
+    )]
+    Projects {
+        #[command(subcommand)]
+        command: Option<projects::ProjectsCommand>,
+    },
+
+    #[command(about = "Check the vaults against the secrets declared in obscura.toml")]
+    Check(check::CheckArgs),
+
+    #[command(about = "Search aliases by name and metadata across vaults")]
+    Search(search::SearchArgs),
+
+    #[command(about = "Add or remove tags on an alias")]
+    Tag(tag::TagArgs),
+
     #[command(about = "Remove a secret from the vault")]
     Remove(remove::RemoveArgs),
 
@@ -49,6 +79,12 @@ enum Commands {
     #[command(about = "Rotate a secret in the vault")]
     Rotate(rotate::RotateArgs),
 
+    #[command(about = "Rename or move aliases within or across vaults")]
+    Mv(transfer::TransferArgs),
+
+    #[command(about = "Copy aliases within or across vaults")]
+    Cp(transfer::TransferArgs),
+
     #[command(about = "Export secrets as dotenv content")]
     Export {
         #[arg(long, help = "Export in dotenv format")]
@@ -57,6 +93,8 @@ enum Commands {
         global: bool,
         #[arg(long, short = 'p', help = "Use the project vault")]
         project: bool,
+        #[arg(long, conflicts_with_all = ["global", "project"], help = "Merge project vaults over the global vault")]
+        layered: bool,
         #[arg(long, help = "Destination file path")]
         output: Option<String>,
         #[arg(long, help = "Allow overwriting the destination file")]
@@ -66,6 +104,27 @@ enum Commands {
     #[command(about = "Run a command with secrets injected as environment variables")]
     Run(run::RunArgs),
 
+    #[command(about = "Render a config file from a template with {{ ALIAS }} placeholders")]
+    Render(render::RenderArgs),
+
+    #[command(about = "Start your shell with secrets injected as environment variables")]
+    Shell(shell::ShellArgs),
+
+    #[command(about = "Print shell statements that set (or unset) the secrets, for eval")]
+    Env(env::EnvArgs),
+
+    #[command(about = "Print the current one-time password for an OTP secret")]
+    Otp(otp::OtpArgs),
+
+    #[command(about = "Generate a random secret value")]
+    Generate(generate::GenerateArgs),
+
+    #[command(about = "Three-way merge of vault files, for use as a git merge driver")]
+    GitMergeDriver(git::GitMergeDriverArgs),
+
+    #[command(about = "Print vault aliases and metadata without values, for git diff")]
+    GitTextconv(git::GitTextconvArgs),
+
     #[command(about = "Clear cached vault keys")]
     Lock(lock::LockArgs),
 
@@ -75,6 +134,7 @@ enum Commands {
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
+    vault::manager::set_environment(cli.env)?;
 
     match cli.command {
         Commands::Init(args) => handle_init(args)?,
@@ -83,13 +143,21 @@ fn main() -> Result<()> {
         Commands::Get(args) => handle_get(args)?,
         Commands::List(args) => handle_list(args)?,
         Commands::Ls(args) => handle_list(args)?,
+        Commands::Status(args) => handle_status(args)?,
+        Commands::Projects { command } => handle_projects(command)?,
+        Commands::Check(args) => handle_check(args)?,
+        Commands::Search(args) => handle_search(args)?,
+        Commands::Tag(args) => handle_tag(args)?,
         Commands::Remove(args) => handle_remove(args)?,
         Commands::Delete(args) => handle_delete(args)?,
         Commands::Rotate(args) => handle_rotate(args)?,
+        Commands::Mv(args) => handle_mv(args)?,
+        Commands::Cp(args) => handle_cp(args)?,
         Commands::Export {
             dotenv,
             global,
             project,
+            layered,
             output,
             overwrite,
         } => {
@@ -97,6 +165,7 @@ fn main() -> Result<()> {
                 handle_export_dotenv(export_dotenv::ExportDotenvArgs {
                     global,
                     project,
+                    layered,
                     output,
                     overwrite,
                 })?;
@@ -106,6 +175,13 @@ fn main() -> Result<()> {
             }
         }
         Commands::Run(args) => handle_run(args)?,
+        Commands::Render(args) => handle_render(args)?,
+        Commands::Shell(args) => handle_shell(args)?,
+        Commands::Env(args) => handle_env(args)?,
+        Commands::Otp(args) => handle_otp(args)?,
+        Commands::Generate(args) => handle_generate(args)?,
+        Commands::GitMergeDriver(args) => handle_git_merge_driver(args)?,
+        Commands::GitTextconv(args) => handle_git_textconv(args)?,
         Commands::Lock(args) => handle_lock(args)?,
         Commands::Unlock(args) => handle_unlock(args)?,
     }
@@ -12,6 +12,9 @@ pub enum ObscuraError {
     ConfirmationMismatch,
     FileExists(String),
     InvalidTimeout,
+    BinaryValue(String),
+    UnresolvedReference(String, String),
+    ReferenceCycle(String),
     CustomError(String),
 }
 
@@ -32,6 +35,19 @@ impl fmt::Display for ObscuraError {
             }
             ObscuraError::FileExists(path) => write!(f, "File '{}' already exists", path),
             ObscuraError::InvalidTimeout => write!(f, "Invalid timeout value"),
+            ObscuraError::BinaryValue(alias) => write!(
+                f,
+                "Alias '{}' holds a file secret; use --output to write it to a file",
+                alias
+            ),
+            ObscuraError::UnresolvedReference(alias, reference) => write!(
+                f,
+                "Alias '{}' references '{}', which was not found",
+                alias, reference
+            ),
+            ObscuraError::ReferenceCycle(chain) => {
+                write!(f, "Reference cycle detected: {}", chain)
+            }
             ObscuraError::CustomError(msg) => write!(f, "{}", msg),
         }
     }
@@ -1,7 +1,10 @@
 use crate::util::errors::{ObscuraError, ObscuraResult};
+use rand::{rngs::OsRng, RngCore};
 use rpassword::read_password;
 use std::env;
+use std::fs::{self, OpenOptions};
 use std::io::{self, Write};
+use std::path::Path;
 
 pub fn prompt_passphrase() -> ObscuraResult<String> {
     print!("Enter vault passphrase (min 8 chars): ");
@@ -44,6 +47,19 @@ pub fn prompt_yes_no(message: &str) -> ObscuraResult<bool> {
     io::stdout()
         .flush()
         .map_err(|_| ObscuraError::FilePermissionError)?;
+    read_yes_no()
+}
+
+// For commands whose stdout is evaluated or piped.
+pub fn prompt_yes_no_on_stderr(message: &str) -> ObscuraResult<bool> {
+    eprint!("{} (y/N): ", message);
+    io::stderr()
+        .flush()
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+    read_yes_no()
+}
+
+fn read_yes_no() -> ObscuraResult<bool> {
     let mut input = String::new();
     io::stdin()
         .read_line(&mut input)
@@ -54,3 +70,74 @@ pub fn prompt_yes_no(message: &str) -> ObscuraResult<bool> {
 pub fn get_passphrase_from_env() -> Option<String> {
     std::env::var("OBSCURA_PASSPHRASE").ok()
 }
+
+pub fn read_secret_file(path: &Path) -> ObscuraResult<Vec<u8>> {
+    if !path.is_file() {
+        return Err(ObscuraError::CustomError(format!(
+            "'{}' is not a file",
+            path.display()
+        )));
+    }
+    fs::read(path)
+        .map_err(|_| ObscuraError::CustomError(format!("Failed to read '{}'", path.display())))
+}
+
+pub fn write_private_file(path: &Path, data: &[u8]) -> ObscuraResult<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(path)
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    file.write_all(data)
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+    Ok(())
+}
+
+// Readers see either the old file or the complete new one, never a partly
+// written file. The data goes to a new file with an unpredictable name next
+// to `path`, so nothing already there (such as a planted symlink) is written
+// through, and reaches the disk before it replaces `path`.
+pub fn write_private_file_atomically(path: &Path, data: &[u8]) -> ObscuraResult<()> {
+    let file_name = path
+        .file_name()
+        .ok_or(ObscuraError::FilePermissionError)?
+        .to_string_lossy();
+    let mut suffix = [0u8; 8];
+    OsRng.fill_bytes(&mut suffix);
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}.obscura-tmp",
+        file_name,
+        hex::encode(suffix)
+    ));
+
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options
+        .open(&temp_path)
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+    let written = file.write_all(data).and_then(|_| file.sync_all());
+    drop(file);
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return Err(ObscuraError::FilePermissionError);
+    }
+    fs::rename(&temp_path, path).map_err(|_| {
+        let _ = fs::remove_file(&temp_path);
+        ObscuraError::FilePermissionError
+    })
+}
@@ -1,3 +1,10 @@
 pub mod errors;
 pub mod io;
 pub mod paths;
+pub mod pattern;
+pub mod process;
+pub mod redact;
+pub mod secret_pipe;
+pub mod tempdir;
+pub mod time;
+pub mod watch;
@@ -1,4 +1,5 @@
 use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::identity::project_identity;
 use directories::ProjectDirs;
 use std::path::{Path, PathBuf};
 
@@ -19,28 +20,25 @@ pub fn get_projects_dir() -> ObscuraResult<PathBuf> {
     Ok(config_dir.join("projects"))
 }
 
-pub fn get_project_vault_path(project_path: &Path) -> ObscuraResult<PathBuf> {
-    let canonical_path = project_path
-        .canonicalize()
-        .map_err(|_| ObscuraError::FilePermissionError)?;
-
-    let hash = blake3::hash(canonical_path.to_string_lossy().as_bytes());
-    let projects_dir = get_projects_dir()?;
-    Ok(projects_dir
-        .join(hex::encode(hash.as_bytes()))
-        .join("vault.enc"))
+pub fn get_project_vault_path(
+    project_path: &Path,
+    environment: Option<&str>,
+) -> ObscuraResult<PathBuf> {
+    let project_dir = get_project_dir(&project_identity(project_path)?.key())?;
+    Ok(match environment {
+        Some(environment) => project_dir
+            .join("envs")
+            .join(format!("{}.enc", environment)),
+        None => project_dir.join("vault.enc"),
+    })
 }
 
 pub fn get_project_meta_path(project_path: &Path) -> ObscuraResult<PathBuf> {
-    let canonical_path = project_path
-        .canonicalize()
-        .map_err(|_| ObscuraError::FilePermissionError)?;
+    Ok(get_project_dir(&project_identity(project_path)?.key())?.join("meta.json"))
+}
 
-    let hash = blake3::hash(canonical_path.to_string_lossy().as_bytes());
-    let projects_dir = get_projects_dir()?;
-    Ok(projects_dir
-        .join(hex::encode(hash.as_bytes()))
-        .join("meta.json"))
+pub fn get_project_dir(key: &str) -> ObscuraResult<PathBuf> {
+    Ok(get_projects_dir()?.join(key))
 }
 
 pub fn ensure_config_dir() -> ObscuraResult<()> {
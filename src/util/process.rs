@@ -0,0 +1,198 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use std::process::{Child, Command, ExitStatus};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[cfg(unix)]
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+
+// A spawned child that receives the termination signals sent to obscura.
+pub struct RunningChild {
+    pub child: Child,
+    #[cfg(unix)]
+    forwarder: unix::Forwarder,
+}
+
+impl RunningChild {
+    pub fn wait(mut self) -> ObscuraResult<ExitStatus> {
+        let status = self.child.wait();
+        #[cfg(unix)]
+        self.forwarder.stop();
+        Ok(status?)
+    }
+
+    pub fn try_wait(&mut self) -> ObscuraResult<Option<ExitStatus>> {
+        Ok(self.child.try_wait()?)
+    }
+
+    // Sends `signal` and gives the child `timeout` to exit before killing it.
+    pub fn stop(mut self, signal: i32, timeout: Duration) -> ObscuraResult<ExitStatus> {
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(self.child.id() as libc::pid_t, signal);
+        }
+        #[cfg(not(unix))]
+        let _ = signal;
+
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            if self.child.try_wait()?.is_some() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        // Fails only when the child has already exited.
+        let _ = self.child.kill();
+        self.wait()
+    }
+}
+
+// Accepts names with or without the SIG prefix, e.g. `TERM` or `SIGINT`.
+// Outside Unix, stopping a child always kills it and the name is unused.
+pub fn parse_signal(name: &str) -> ObscuraResult<i32> {
+    let upper = name.to_ascii_uppercase();
+    let bare = upper.strip_prefix("SIG").unwrap_or(&upper);
+    #[cfg(unix)]
+    let signal = match bare {
+        "INT" => Some(libc::SIGINT),
+        "TERM" => Some(libc::SIGTERM),
+        "HUP" => Some(libc::SIGHUP),
+        "QUIT" => Some(libc::SIGQUIT),
+        "KILL" => Some(libc::SIGKILL),
+        "USR1" => Some(libc::SIGUSR1),
+        "USR2" => Some(libc::SIGUSR2),
+        _ => None,
+    };
+    #[cfg(not(unix))]
+    let signal = match bare {
+        "INT" | "TERM" | "HUP" | "QUIT" | "KILL" | "USR1" | "USR2" => Some(0),
+        _ => None,
+    };
+    signal.ok_or_else(|| {
+        ObscuraError::CustomError(format!(
+            "Unknown signal '{}': expected INT, TERM, HUP, QUIT, KILL, USR1 or USR2",
+            name
+        ))
+    })
+}
+
+// On Unix, SIGINT, SIGTERM, SIGHUP and SIGQUIT sent to obscura are passed
+// on to the child instead of killing obscura first.
+pub fn spawn_forwarding_signals(command: &mut Command) -> ObscuraResult<RunningChild> {
+    #[cfg(unix)]
+    {
+        // Registered before spawning so no signal slips through in between.
+        let forwarder = unix::Forwarder::register()?;
+        let child = command.spawn().map_err(|err| spawn_error(command, err))?;
+        Ok(RunningChild {
+            forwarder: forwarder.start(child.id()),
+            child,
+        })
+    }
+    #[cfg(not(unix))]
+    {
+        let child = command.spawn().map_err(|err| spawn_error(command, err))?;
+        Ok(RunningChild { child })
+    }
+}
+
+// Replaces obscura with `command`, so signals and the exit status need no
+// relaying. Only returns on failure.
+#[cfg(unix)]
+pub fn exec(command: &mut Command) -> ObscuraError {
+    let err = command.exec();
+    spawn_error(command, err)
+}
+
+fn spawn_error(command: &Command, err: std::io::Error) -> ObscuraError {
+    ObscuraError::CustomError(format!(
+        "Failed to run '{}': {}",
+        command.get_program().to_string_lossy(),
+        err
+    ))
+}
+
+// Shell convention: the child's exit code, or 128+N when signal N killed it.
+pub fn exit_code(status: ExitStatus) -> i32 {
+    #[cfg(unix)]
+    if let Some(signal) = status.signal() {
+        return 128 + signal;
+    }
+    status.code().unwrap_or(1)
+}
+
+// Ends obscura the way the child ended. Terminating signals are re-raised so
+// that a parent shell sees the death by signal, e.g. to stop a script on ^C.
+pub fn exit_like(status: ExitStatus) -> ! {
+    #[cfg(unix)]
+    if let Some(signal) = status.signal() {
+        unix::reraise(signal);
+    }
+    std::process::exit(exit_code(status))
+}
+
+#[cfg(unix)]
+mod unix {
+    use crate::util::errors::ObscuraResult;
+    use signal_hook::consts::{SIGHUP, SIGINT, SIGKILL, SIGPIPE, SIGQUIT, SIGTERM};
+    use signal_hook::iterator::backend::Handle;
+    use signal_hook::iterator::exfiltrator::WithOrigin;
+    use signal_hook::iterator::SignalsInfo;
+    use signal_hook::low_level::siginfo::Cause;
+    use std::thread::{self, JoinHandle};
+
+    const FORWARDED: [i32; 4] = [SIGINT, SIGTERM, SIGHUP, SIGQUIT];
+    // Signals whose default action ends the process without a core dump.
+    const RERAISED: [i32; 5] = [SIGINT, SIGTERM, SIGHUP, SIGPIPE, SIGKILL];
+
+    pub struct Forwarder {
+        signals: Option<SignalsInfo<WithOrigin>>,
+        handle: Handle,
+        thread: Option<JoinHandle<()>>,
+    }
+
+    impl Forwarder {
+        pub fn register() -> ObscuraResult<Self> {
+            let signals = SignalsInfo::<WithOrigin>::new(FORWARDED)?;
+            Ok(Self {
+                handle: signals.handle(),
+                signals: Some(signals),
+                thread: None,
+            })
+        }
+
+        pub fn start(mut self, child_id: u32) -> Self {
+            let pid = child_id as libc::pid_t;
+            if let Some(mut signals) = self.signals.take() {
+                self.thread = Some(thread::spawn(move || {
+                    for origin in signals.forever() {
+                        // Signals from the terminal (^C, ^\, hangup) already reach
+                        // the whole foreground process group; only relay ones sent to us.
+                        if !matches!(origin.cause, Cause::Kernel) {
+                            unsafe {
+                                libc::kill(pid, origin.signal);
+                            }
+                        }
+                    }
+                }));
+            }
+            self
+        }
+
+        pub fn stop(&mut self) {
+            self.handle.close();
+            if let Some(thread) = self.thread.take() {
+                let _ = thread.join();
+            }
+        }
+    }
+
+    pub fn reraise(signal: i32) {
+        if RERAISED.contains(&signal) {
+            unsafe {
+                libc::signal(signal, libc::SIG_DFL);
+                libc::raise(signal);
+            }
+        }
+    }
+}
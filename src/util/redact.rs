@@ -0,0 +1,185 @@
+use crate::vault::template::URL_UNRESERVED;
+use base64::{engine::general_purpose, Engine as _};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+// Shorter values would redact ordinary output (`1`, `yes`, ...).
+pub const MIN_REDACTED_LEN: usize = 4;
+// How long a possible start of a secret is held back when no more output
+// follows, so prompts such as `Password:` are not stuck behind it.
+const IDLE_FLUSH: Duration = Duration::from_millis(100);
+
+// Replaces secret values, and their base64 and URL-encoded forms, with
+// `***ALIAS***`.
+pub struct Redactor {
+    // Longest first, so the longest match at a position wins.
+    needles: Vec<(Vec<u8>, Vec<u8>)>,
+    longest: usize,
+}
+
+impl Redactor {
+    pub fn new(secrets: &[(String, Vec<u8>)]) -> Self {
+        let mut needles: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        for (alias, value) in secrets {
+            if value.len() < MIN_REDACTED_LEN {
+                continue;
+            }
+            let replacement = format!("***{}***", alias).into_bytes();
+            for form in encoded_forms(value) {
+                if !needles.iter().any(|(needle, _)| *needle == form) {
+                    needles.push((form, replacement.clone()));
+                }
+            }
+        }
+        needles.sort_by_key(|(needle, _)| std::cmp::Reverse(needle.len()));
+        let longest = needles.first().map(|(needle, _)| needle.len()).unwrap_or(0);
+        Self { needles, longest }
+    }
+
+    pub fn stream(&self) -> RedactStream<'_> {
+        RedactStream {
+            redactor: self,
+            pending: Vec::new(),
+            shown: 0,
+        }
+    }
+
+    // Copies `input` to `output` redacted, flushing after every read so the
+    // child's own line buffering is kept. Held-back bytes are written once
+    // the input has been idle for a moment.
+    pub fn pump(
+        &self,
+        mut input: impl Read + Send + 'static,
+        mut output: impl Write,
+    ) -> io::Result<()> {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut buffer = [0u8; 8192];
+            loop {
+                let read = match input.read(&mut buffer) {
+                    Ok(0) => break,
+                    Ok(read) => read,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(err) => {
+                        let _ = sender.send(Err(err));
+                        break;
+                    }
+                };
+                if sender.send(Ok(buffer[..read].to_vec())).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut stream = self.stream();
+        loop {
+            let chunk = match receiver.recv_timeout(IDLE_FLUSH) {
+                Ok(chunk) => chunk?,
+                Err(RecvTimeoutError::Timeout) => {
+                    let held = stream.release();
+                    if !held.is_empty() {
+                        output.write_all(&held)?;
+                        output.flush()?;
+                    }
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            output.write_all(&stream.push(&chunk))?;
+            output.flush()?;
+        }
+        output.write_all(&stream.finish())?;
+        output.flush()
+    }
+}
+
+// Redaction state for one output stream. Bytes that could still be the start
+// of a secret continued in the next chunk are held back until it arrives.
+pub struct RedactStream<'a> {
+    redactor: &'a Redactor,
+    pending: Vec<u8>,
+    // Leading bytes of `pending` already written by `release`. They are still
+    // matched against, so the rest of a secret that follows is masked.
+    shown: usize,
+}
+
+impl RedactStream<'_> {
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(chunk);
+        let (output, consumed) = self.scan(false);
+        self.pending.drain(..consumed);
+        self.shown = self.shown.saturating_sub(consumed);
+        output
+    }
+
+    // Writes out the held-back bytes without waiting for the next chunk.
+    pub fn release(&mut self) -> Vec<u8> {
+        let held = self.pending[self.shown..].to_vec();
+        self.shown = self.pending.len();
+        held
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        let (output, _) = self.scan(true);
+        self.pending.clear();
+        self.shown = 0;
+        output
+    }
+
+    fn scan(&self, at_end: bool) -> (Vec<u8>, usize) {
+        let data = &self.pending;
+        let mut output = Vec::with_capacity(data.len());
+        let mut index = 0;
+
+        'scan: while index < data.len() {
+            let rest = &data[index..];
+            for (needle, replacement) in &self.redactor.needles {
+                if rest.starts_with(needle) {
+                    output.extend_from_slice(replacement);
+                    index += needle.len();
+                    continue 'scan;
+                }
+            }
+            if !at_end
+                && rest.len() < self.redactor.longest
+                && self
+                    .redactor
+                    .needles
+                    .iter()
+                    .any(|(needle, _)| needle.len() > rest.len() && needle.starts_with(rest))
+            {
+                break;
+            }
+            if index >= self.shown {
+                output.push(data[index]);
+            }
+            index += 1;
+        }
+
+        (output, index)
+    }
+}
+
+fn encoded_forms(value: &[u8]) -> Vec<Vec<u8>> {
+    let mut forms = vec![
+        value.to_vec(),
+        general_purpose::STANDARD_NO_PAD.encode(value).into_bytes(),
+        general_purpose::URL_SAFE_NO_PAD.encode(value).into_bytes(),
+    ];
+    if let Ok(text) = std::str::from_utf8(value) {
+        forms.push(
+            utf8_percent_encode(text, URL_UNRESERVED)
+                .to_string()
+                .into_bytes(),
+        );
+        forms.push(
+            utf8_percent_encode(text, NON_ALPHANUMERIC)
+                .to_string()
+                .into_bytes(),
+        );
+    }
+    forms
+}
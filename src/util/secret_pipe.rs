@@ -0,0 +1,113 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::tempdir::PrivateTempDir;
+use std::fs::OpenOptions;
+use std::io::{self, PipeReader, PipeWriter, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+
+// A secret the child reads from a path instead of its environment: a pipe it
+// inherits, opened as /dev/fd/N, or a FIFO in the private temp dir where
+// /dev/fd is unavailable. The value stays in memory and can be read once.
+pub struct SecretPipe {
+    path: PathBuf,
+    channel: Channel,
+    value: Vec<u8>,
+}
+
+enum Channel {
+    Pipe {
+        reader: PipeReader,
+        writer: PipeWriter,
+    },
+    Fifo,
+}
+
+impl SecretPipe {
+    pub fn create(
+        name: &str,
+        value: Vec<u8>,
+        secret_dir: &mut Option<PrivateTempDir>,
+    ) -> ObscuraResult<Self> {
+        #[cfg(unix)]
+        {
+            if Path::new("/dev/fd").is_dir() {
+                let (reader, writer) = io::pipe()?;
+                let fd = inheritable(&reader)?;
+                return Ok(Self {
+                    path: PathBuf::from(format!("/dev/fd/{}", fd)),
+                    channel: Channel::Pipe { reader, writer },
+                    value,
+                });
+            }
+
+            if secret_dir.is_none() {
+                *secret_dir = Some(PrivateTempDir::create()?);
+            }
+            let dir = secret_dir.as_ref().expect("secret dir was just created");
+            Ok(Self {
+                path: dir.create_fifo(name)?,
+                channel: Channel::Fifo,
+                value,
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = (name, value, secret_dir);
+            Err(ObscuraError::CustomError(
+                "--as-file is only supported on Unix".to_string(),
+            ))
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Called once the child is running. Writing happens in the background
+    // since it blocks until the child reads; the child sees end of file
+    // after the value.
+    pub fn feed(self) {
+        let Self {
+            path,
+            channel,
+            value,
+        } = self;
+        let writer = match channel {
+            Channel::Pipe { reader, writer } => {
+                // Only the child may hold the read end, or a child that exits
+                // without reading would leave the writer blocked.
+                drop(reader);
+                Some(writer)
+            }
+            Channel::Fifo => None,
+        };
+
+        thread::spawn(move || {
+            let result = match writer {
+                Some(mut writer) => writer.write_all(&value),
+                // Opening a FIFO for writing waits for the child to open it.
+                None => OpenOptions::new()
+                    .write(true)
+                    .open(&path)
+                    .and_then(|mut fifo| fifo.write_all(&value)),
+            };
+            // A child that never reads its secret is not an error.
+            let _ = result;
+        });
+    }
+}
+
+// Pipes are created close-on-exec; the read end has to survive into the child.
+#[cfg(unix)]
+fn inheritable(reader: &PipeReader) -> ObscuraResult<i32> {
+    use std::os::fd::AsRawFd;
+
+    let fd = reader.as_raw_fd();
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, 0) } == -1 {
+        return Err(ObscuraError::CustomError(format!(
+            "Failed to pass a pipe to the command: {}",
+            io::Error::last_os_error()
+        )));
+    }
+    Ok(fd)
+}
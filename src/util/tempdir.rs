@@ -0,0 +1,139 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::util::io::write_private_file;
+use rand::{rngs::OsRng, RngCore};
+use std::cell::Cell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub struct PrivateTempDir {
+    path: PathBuf,
+    wiped: bool,
+    next_index: Cell<usize>,
+}
+
+impl PrivateTempDir {
+    pub fn create() -> ObscuraResult<Self> {
+        let mut suffix = [0u8; 8];
+        OsRng.fill_bytes(&mut suffix);
+        let name = format!("obscura-{}", hex::encode(suffix));
+
+        for base in candidate_bases() {
+            let path = base.join(&name);
+            if create_private_dir(&path).is_ok() {
+                return Ok(Self {
+                    path,
+                    wiped: false,
+                    next_index: Cell::new(0),
+                });
+            }
+        }
+
+        Err(ObscuraError::FilePermissionError)
+    }
+
+    pub fn write_file(&self, name: &str, data: &[u8]) -> ObscuraResult<PathBuf> {
+        let path = self.unique_path(name);
+        write_private_file(&path, data)?;
+        Ok(path)
+    }
+
+    #[cfg(unix)]
+    pub fn create_fifo(&self, name: &str) -> ObscuraResult<PathBuf> {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = self.unique_path(name);
+        let c_path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|_| ObscuraError::FilePermissionError)?;
+        if unsafe { libc::mkfifo(c_path.as_ptr(), 0o600) } != 0 {
+            return Err(ObscuraError::FilePermissionError);
+        }
+        Ok(path)
+    }
+
+    // Aliases such as "a/b" and "a_b" sanitize to the same name, so each file
+    // gets an index prefix that keeps it distinct while preserving the extension.
+    fn unique_path(&self, name: &str) -> PathBuf {
+        let index = self.next_index.get();
+        self.next_index.set(index + 1);
+        self.path
+            .join(format!("{}-{}", index, sanitize_file_name(name)))
+    }
+
+    pub fn wipe(&mut self) {
+        if self.wiped {
+            return;
+        }
+        self.wiped = true;
+
+        if let Ok(entries) = fs::read_dir(&self.path) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.is_file() {
+                    overwrite_with_zeros(&path);
+                }
+            }
+        }
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+impl Drop for PrivateTempDir {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+fn candidate_bases() -> Vec<PathBuf> {
+    let mut bases = Vec::new();
+    // Prefer memory-backed locations so secrets never reach a physical disk.
+    #[cfg(target_os = "linux")]
+    {
+        let shm = PathBuf::from("/dev/shm");
+        if shm.is_dir() {
+            bases.push(shm);
+        }
+    }
+    if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR") {
+        let runtime_dir = PathBuf::from(runtime_dir);
+        if runtime_dir.is_dir() {
+            bases.push(runtime_dir);
+        }
+    }
+    bases.push(std::env::temp_dir());
+    bases
+}
+
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    builder.create(path)
+}
+
+fn overwrite_with_zeros(path: &Path) {
+    let len = match fs::metadata(path) {
+        Ok(metadata) => metadata.len() as usize,
+        Err(_) => return,
+    };
+    if let Ok(mut file) = OpenOptions::new().write(true).open(path) {
+        let _ = file.write_all(&vec![0u8; len]);
+        let _ = file.sync_all();
+    }
+}
+
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
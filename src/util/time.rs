@@ -0,0 +1,32 @@
+use chrono::{DateTime, Utc};
+
+pub fn parse_timestamp(value: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+}
+
+pub fn age_seconds(timestamp: &str) -> Option<i64> {
+    parse_timestamp(timestamp).map(|timestamp| (Utc::now() - timestamp).num_seconds().max(0))
+}
+
+// The date part of an RFC 3339 timestamp.
+pub fn short_date(timestamp: &str) -> &str {
+    timestamp.get(..10).unwrap_or(timestamp)
+}
+
+// Compact, human-readable age such as `45s`, `3h`, `12d`, `5mo` or `2y`.
+pub fn format_age(seconds: i64) -> String {
+    const MINUTE: i64 = 60;
+    const HOUR: i64 = 60 * MINUTE;
+    const DAY: i64 = 24 * HOUR;
+
+    match seconds {
+        s if s < MINUTE => format!("{}s", s),
+        s if s < HOUR => format!("{}m", s / MINUTE),
+        s if s < DAY => format!("{}h", s / HOUR),
+        s if s < 60 * DAY => format!("{}d", s / DAY),
+        s if s < 365 * DAY => format!("{}mo", s / (30 * DAY)),
+        s => format!("{}y", s / (365 * DAY)),
+    }
+}
@@ -0,0 +1,171 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+// Notices changes to a set of files: through inotify on Linux, by comparing
+// modification times and sizes elsewhere or when inotify is unavailable.
+pub struct FileWatcher {
+    paths: Vec<PathBuf>,
+    snapshot: Vec<Option<(SystemTime, u64)>>,
+    #[cfg(target_os = "linux")]
+    inotify: Option<inotify::Inotify>,
+}
+
+impl FileWatcher {
+    pub fn new(paths: Vec<PathBuf>) -> Self {
+        #[cfg(target_os = "linux")]
+        let inotify = inotify::Inotify::watch(&paths);
+        let mut watcher = Self {
+            snapshot: Vec::new(),
+            paths,
+            #[cfg(target_os = "linux")]
+            inotify,
+        };
+        watcher.reset();
+        watcher
+    }
+
+    // Waits up to `timeout` and reports whether any of the files changed.
+    pub fn wait(&mut self, timeout: Duration) -> bool {
+        #[cfg(target_os = "linux")]
+        if let Some(inotify) = &mut self.inotify {
+            return inotify.wait(timeout);
+        }
+
+        thread::sleep(timeout);
+        let snapshot = stamps(&self.paths);
+        if snapshot == self.snapshot {
+            return false;
+        }
+        self.snapshot = snapshot;
+        true
+    }
+
+    // Returns once the files have not changed for `quiet`, so that a burst
+    // of writes counts as one change.
+    pub fn settle(&mut self, quiet: Duration) {
+        while self.wait(quiet) {}
+    }
+
+    // Forgets changes seen so far, e.g. ones obscura made itself.
+    pub fn reset(&mut self) {
+        #[cfg(target_os = "linux")]
+        if let Some(inotify) = &mut self.inotify {
+            while inotify.wait(Duration::ZERO) {}
+            return;
+        }
+
+        self.snapshot = stamps(&self.paths);
+    }
+}
+
+fn stamps(paths: &[PathBuf]) -> Vec<Option<(SystemTime, u64)>> {
+    paths.iter().map(|path| stamp(path)).collect()
+}
+
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+#[cfg(target_os = "linux")]
+mod inotify {
+    use std::ffi::{CString, OsStr, OsString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    // Vaults are replaced by renaming a new file over them, so the
+    // directories are watched rather than the files themselves.
+    const EVENTS: u32 = libc::IN_CLOSE_WRITE
+        | libc::IN_MOVED_TO
+        | libc::IN_MOVED_FROM
+        | libc::IN_CREATE
+        | libc::IN_DELETE;
+
+    pub struct Inotify {
+        fd: libc::c_int,
+        // Watch descriptor and file name of every watched file.
+        files: Vec<(libc::c_int, OsString)>,
+    }
+
+    impl Inotify {
+        pub fn watch(paths: &[PathBuf]) -> Option<Self> {
+            let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+            if fd < 0 {
+                return None;
+            }
+            let mut inotify = Self {
+                fd,
+                files: Vec::new(),
+            };
+
+            for path in paths {
+                let (dir, name) = match (path.parent(), path.file_name()) {
+                    (Some(dir), Some(name)) => (dir, name),
+                    _ => return None,
+                };
+                let dir = CString::new(dir.as_os_str().as_bytes()).ok()?;
+                let wd = unsafe { libc::inotify_add_watch(fd, dir.as_ptr(), EVENTS) };
+                if wd < 0 {
+                    return None;
+                }
+                inotify.files.push((wd, name.to_os_string()));
+            }
+
+            Some(inotify)
+        }
+
+        pub fn wait(&mut self, timeout: Duration) -> bool {
+            let mut pollfd = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout = timeout.as_millis().min(libc::c_int::MAX as u128) as libc::c_int;
+            if unsafe { libc::poll(&mut pollfd, 1, timeout) } <= 0 {
+                return false;
+            }
+
+            let mut changed = false;
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = unsafe { libc::read(self.fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+                if read <= 0 {
+                    break;
+                }
+                changed |= self.matches(&buffer[..read as usize]);
+            }
+            changed
+        }
+
+        // Whether a batch of events touches one of the watched files rather
+        // than something else in the same directory.
+        fn matches(&self, mut events: &[u8]) -> bool {
+            let header = std::mem::size_of::<libc::inotify_event>();
+            let mut matched = false;
+            while events.len() >= header {
+                let event: libc::inotify_event =
+                    unsafe { std::ptr::read_unaligned(events.as_ptr().cast()) };
+                let end = (header + event.len as usize).min(events.len());
+                let name = events[header..end].split(|byte| *byte == 0).next();
+                if let Some(name) = name {
+                    matched |= self.files.iter().any(|(wd, file)| {
+                        *wd == event.wd && file.as_os_str() == OsStr::from_bytes(name)
+                    });
+                }
+                events = &events[end..];
+            }
+            matched
+        }
+    }
+
+    impl Drop for Inotify {
+        fn drop(&mut self) {
+            unsafe {
+                libc::close(self.fd);
+            }
+        }
+    }
+}
@@ -1,9 +1,13 @@
 use crate::crypto::aead::AeadKey;
 use crate::crypto::kdf::{KdfParams, KdfResult};
 use crate::util::errors::{ObscuraError, ObscuraResult};
-use crate::vault::model::{AliasesData, EncryptedData, VaultBody, VaultFile};
+use crate::vault::model::{
+    AliasData, AliasesData, EncryptedData, SealedAlias, VaultBody, VaultFile, VaultStorage,
+};
 use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
 use serde_json;
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
 
@@ -20,7 +24,11 @@ pub fn create_vault_file(path: &Path, passphrase: &str) -> ObscuraResult<()> {
     };
 
     let dek_wrapped = wrap_dek(&dek, &kdf_result.key)?;
-    let vault_file = VaultFile::new(kdf_result.params.clone(), dek_wrapped, body);
+    let vault_file = VaultFile::new(
+        kdf_result.params.clone(),
+        dek_wrapped,
+        VaultStorage::Sealed { body },
+    );
     write_vault_atomically(path, &vault_file)
 }
 
@@ -30,6 +38,20 @@ pub fn read_vault_file(path: &Path) -> ObscuraResult<VaultFile> {
     Ok(vault_file)
 }
 
+// In-repo vaults keep each alias as its own sealed entry so that git diffs
+// and merges stay per-alias instead of rewriting one opaque blob.
+pub fn create_repo_vault_file(path: &Path, passphrase: &str) -> ObscuraResult<()> {
+    let kdf_result = KdfResult::derive(passphrase)?;
+    let dek = AeadKey::new();
+    let dek_wrapped = wrap_dek(&dek, &kdf_result.key)?;
+    let vault_file = VaultFile::new(
+        kdf_result.params.clone(),
+        dek_wrapped,
+        VaultStorage::empty_entries(),
+    );
+    encrypt_and_save_vault(path, &vault_file, &AliasesData::new(), &dek)
+}
+
 pub fn decrypt_vault(
     vault_file: &VaultFile,
     passphrase: &str,
@@ -37,35 +59,27 @@ pub fn decrypt_vault(
     let kdf_params: KdfParams = vault_file.kdf.clone().into();
     let kdf_result = KdfResult::derive_with_params(passphrase, &kdf_params)?;
     let dek = unwrap_dek(&vault_file.dek_wrapped, &kdf_result.key)?;
-
-    let body_nonce = general_purpose::STANDARD
-        .decode(&vault_file.body.nonce_b64)
-        .map_err(|_| ObscuraError::DecryptionFailed)?;
-    let body_ciphertext = general_purpose::STANDARD
-        .decode(&vault_file.body.ciphertext_b64)
-        .map_err(|_| ObscuraError::DecryptionFailed)?;
-
-    if body_nonce.len() != 24 {
-        return Err(ObscuraError::DecryptionFailed);
-    }
-
-    let mut body_nonce_array = [0u8; 24];
-    body_nonce_array.copy_from_slice(&body_nonce);
-
-    let aliases_json =
-        crate::crypto::aead::decrypt_with_key(&body_ciphertext, &dek, &body_nonce_array, b"")?;
-    let aliases_data: AliasesData =
-        serde_json::from_slice(&aliases_json).map_err(|_| ObscuraError::DecryptionFailed)?;
+    let aliases_data = decrypt_vault_with_dek(vault_file, &dek)?;
 
     Ok((dek, aliases_data))
 }
 
 pub fn decrypt_vault_with_dek(vault_file: &VaultFile, dek: &AeadKey) -> ObscuraResult<AliasesData> {
+    let body = match &vault_file.storage {
+        VaultStorage::Sealed { body } => body,
+        VaultStorage::Entries {
+            revision,
+            mac,
+            entries,
+        } => return open_entries(entries, *revision, mac, dek),
+        VaultStorage::LegacyEntries { aliases } => return open_legacy_entries(aliases, dek),
+    };
+
     let body_nonce = general_purpose::STANDARD
-        .decode(&vault_file.body.nonce_b64)
+        .decode(&body.nonce_b64)
         .map_err(|_| ObscuraError::DecryptionFailed)?;
     let body_ciphertext = general_purpose::STANDARD
-        .decode(&vault_file.body.ciphertext_b64)
+        .decode(&body.ciphertext_b64)
         .map_err(|_| ObscuraError::DecryptionFailed)?;
 
     if body_nonce.len() != 24 {
@@ -89,20 +103,177 @@ pub fn encrypt_and_save_vault(
     aliases_data: &AliasesData,
     dek: &AeadKey,
 ) -> ObscuraResult<()> {
-    let aliases_json =
-        serde_json::to_vec(aliases_data).map_err(|_| ObscuraError::EncryptionFailed)?;
-    let body_aead = crate::crypto::aead::encrypt_with_key(&aliases_json, dek, b"")?;
+    let storage = match &vault_file.storage {
+        VaultStorage::Sealed { .. } => {
+            let aliases_json =
+                serde_json::to_vec(aliases_data).map_err(|_| ObscuraError::EncryptionFailed)?;
+            let body_aead = crate::crypto::aead::encrypt_with_key(&aliases_json, dek, b"")?;
+            VaultStorage::Sealed {
+                body: VaultBody {
+                    nonce_b64: general_purpose::STANDARD.encode(body_aead.nonce),
+                    ciphertext_b64: general_purpose::STANDARD.encode(body_aead.ciphertext),
+                },
+            }
+        }
+        VaultStorage::Entries { revision, .. } => seal_entries(aliases_data, revision + 1, dek)?,
+        VaultStorage::LegacyEntries { .. } => seal_entries(aliases_data, 1, dek)?,
+    };
     let updated_vault = VaultFile {
-        body: VaultBody {
-            nonce_b64: general_purpose::STANDARD.encode(body_aead.nonce),
-            ciphertext_b64: general_purpose::STANDARD.encode(body_aead.ciphertext),
-        },
+        storage,
         ..vault_file.clone()
     };
 
     write_vault_atomically(path, &updated_vault)
 }
 
+// What an entry encrypts: the alias name with all its metadata.
+#[derive(Serialize, Deserialize)]
+struct EntryContent {
+    alias: String,
+    #[serde(flatten)]
+    data: AliasData,
+}
+
+// Entry nonces are derived from the entry itself, so an unchanged alias
+// encrypts to the same bytes and only changed entries show up in diffs.
+pub fn seal_entries(
+    aliases_data: &AliasesData,
+    revision: u64,
+    dek: &AeadKey,
+) -> ObscuraResult<VaultStorage> {
+    let nonce_key = blake3::derive_key("obscura vault entry nonce v2", dek.as_bytes());
+    let mut entries = BTreeMap::new();
+    for (alias, data) in &aliases_data.aliases {
+        let id = entry_id(alias, dek);
+        let content = serde_json::to_vec(&EntryContent {
+            alias: alias.clone(),
+            data: data.clone(),
+        })
+        .map_err(|_| ObscuraError::EncryptionFailed)?;
+
+        let mut hasher = blake3::Hasher::new_keyed(&nonce_key);
+        hasher.update(id.as_bytes());
+        hasher.update(&content);
+        let mut nonce = [0u8; 24];
+        nonce.copy_from_slice(&hasher.finalize().as_bytes()[..24]);
+
+        let sealed = crate::crypto::aead::encrypt_with_nonce(&content, dek, &nonce, id.as_bytes())?;
+        entries.insert(
+            id,
+            EncryptedData {
+                nonce_b64: general_purpose::STANDARD.encode(sealed.nonce),
+                ciphertext_b64: general_purpose::STANDARD.encode(sealed.ciphertext),
+            },
+        );
+    }
+
+    let mac = entries_mac(&entries, revision, dek).to_hex().to_string();
+    Ok(VaultStorage::Entries {
+        revision,
+        mac,
+        entries,
+    })
+}
+
+pub fn open_entries(
+    entries: &BTreeMap<String, EncryptedData>,
+    revision: u64,
+    mac: &str,
+    dek: &AeadKey,
+) -> ObscuraResult<AliasesData> {
+    let mac = blake3::Hash::from_hex(mac).map_err(|_| ObscuraError::DecryptionFailed)?;
+    // blake3::Hash compares in constant time.
+    if mac != entries_mac(entries, revision, dek) {
+        return Err(ObscuraError::CustomError(
+            "Vault entries failed their integrity check (entries were removed, added or edited)"
+                .to_string(),
+        ));
+    }
+
+    let mut aliases_data = AliasesData::new();
+    for (id, sealed) in entries {
+        let content = decrypt_entry(id, sealed, dek)?;
+        let content: EntryContent =
+            serde_json::from_slice(&content).map_err(|_| ObscuraError::DecryptionFailed)?;
+        if entry_id(&content.alias, dek) != *id {
+            return Err(ObscuraError::CustomError(format!(
+                "Vault entry '{}' failed its integrity check",
+                content.alias
+            )));
+        }
+        aliases_data.aliases.insert(content.alias, content.data);
+    }
+    Ok(aliases_data)
+}
+
+// Each entry MAC binds its metadata and ciphertext to the alias name.
+fn open_legacy_entries(
+    entries: &BTreeMap<String, SealedAlias>,
+    dek: &AeadKey,
+) -> ObscuraResult<AliasesData> {
+    let mut aliases_data = AliasesData::new();
+    for (alias, entry) in entries {
+        let mac = blake3::Hash::from_hex(&entry.mac).map_err(|_| ObscuraError::DecryptionFailed)?;
+        if mac != legacy_entry_mac(alias, &entry.data, dek)? {
+            return Err(ObscuraError::CustomError(format!(
+                "Vault entry '{}' failed its integrity check",
+                alias
+            )));
+        }
+        aliases_data
+            .aliases
+            .insert(alias.clone(), entry.data.clone());
+    }
+    Ok(aliases_data)
+}
+
+fn entry_id(alias: &str, dek: &AeadKey) -> String {
+    let key = blake3::derive_key("obscura vault entry id v2", dek.as_bytes());
+    let hash = blake3::keyed_hash(&key, alias.as_bytes());
+    hex::encode(&hash.as_bytes()[..16])
+}
+
+fn decrypt_entry(id: &str, sealed: &EncryptedData, dek: &AeadKey) -> ObscuraResult<Vec<u8>> {
+    let nonce = general_purpose::STANDARD
+        .decode(&sealed.nonce_b64)
+        .map_err(|_| ObscuraError::DecryptionFailed)?;
+    let ciphertext = general_purpose::STANDARD
+        .decode(&sealed.ciphertext_b64)
+        .map_err(|_| ObscuraError::DecryptionFailed)?;
+    let nonce: [u8; 24] = nonce
+        .try_into()
+        .map_err(|_| ObscuraError::DecryptionFailed)?;
+    crate::crypto::aead::decrypt_with_key(&ciphertext, dek, &nonce, id.as_bytes())
+}
+
+fn entries_mac(
+    entries: &BTreeMap<String, EncryptedData>,
+    revision: u64,
+    dek: &AeadKey,
+) -> blake3::Hash {
+    let key = blake3::derive_key("obscura vault entries mac v2", dek.as_bytes());
+    let mut hasher = blake3::Hasher::new_keyed(&key);
+    hasher.update(&revision.to_le_bytes());
+    hasher.update(&(entries.len() as u64).to_le_bytes());
+    for (id, sealed) in entries {
+        for part in [id, &sealed.nonce_b64, &sealed.ciphertext_b64] {
+            hasher.update(&(part.len() as u64).to_le_bytes());
+            hasher.update(part.as_bytes());
+        }
+    }
+    hasher.finalize()
+}
+
+fn legacy_entry_mac(alias: &str, data: &AliasData, dek: &AeadKey) -> ObscuraResult<blake3::Hash> {
+    let key = blake3::derive_key("obscura vault entry mac v1", dek.as_bytes());
+    let data_json = serde_json::to_vec(data).map_err(|_| ObscuraError::EncryptionFailed)?;
+    let mut hasher = blake3::Hasher::new_keyed(&key);
+    hasher.update(&(alias.len() as u64).to_le_bytes());
+    hasher.update(alias.as_bytes());
+    hasher.update(&data_json);
+    Ok(hasher.finalize())
+}
+
 fn wrap_dek(dek: &AeadKey, kek: &[u8; 32]) -> ObscuraResult<EncryptedData> {
     let kek_key = AeadKey::from_bytes(*kek);
     let aead_result = crate::crypto::aead::encrypt_with_key(dek.as_bytes(), &kek_key, b"")?;
@@ -137,7 +308,9 @@ fn unwrap_dek(dek_wrapped: &EncryptedData, kek: &[u8; 32]) -> ObscuraResult<Aead
 
 fn write_vault_atomically(path: &Path, vault_file: &VaultFile) -> ObscuraResult<()> {
     let temp_path = path.with_extension("tmp");
-    let data = serde_json::to_vec_pretty(vault_file).map_err(|_| ObscuraError::EncryptionFailed)?;
+    let mut data =
+        serde_json::to_vec_pretty(vault_file).map_err(|_| ObscuraError::EncryptionFailed)?;
+    data.push(b'\n');
     fs::write(&temp_path, &data).map_err(|_| ObscuraError::FilePermissionError)?;
     #[cfg(unix)]
     {
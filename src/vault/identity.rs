@@ -0,0 +1,209 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const MARKER_FILE: &str = ".obscura";
+const REPO_MARKER_FILE: &str = "project";
+const REPO_VAULT_FILE: &str = "vault.enc";
+const REPO_ENVS_DIR: &str = "envs";
+
+// How a project directory maps to its vault. Without a marker file the
+// canonical path is used, so moving the directory detaches the vault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProjectIdentity {
+    Path(PathBuf),
+    Id(String),
+    GitRemote(String),
+}
+
+impl ProjectIdentity {
+    pub fn key(&self) -> String {
+        let hash = match self {
+            ProjectIdentity::Path(path) => blake3::hash(path.to_string_lossy().as_bytes()),
+            ProjectIdentity::Id(id) => blake3::hash(format!("id:{}", id).as_bytes()),
+            ProjectIdentity::GitRemote(url) => blake3::hash(format!("git:{}", url).as_bytes()),
+        };
+        hex::encode(hash.as_bytes())
+    }
+}
+
+impl fmt::Display for ProjectIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProjectIdentity::Path(path) => write!(f, "path:{}", path.display()),
+            ProjectIdentity::Id(id) => write!(f, "id:{}", id),
+            ProjectIdentity::GitRemote(url) => write!(f, "git:{}", url),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Marker {
+    Id(String),
+    GitRemote(String),
+}
+
+impl Marker {
+    pub fn render(&self) -> String {
+        let entry = match self {
+            Marker::Id(id) => format!("id = {}", id),
+            Marker::GitRemote(remote) => format!("git-remote = {}", remote),
+        };
+        format!(
+            "# Obscura project identity. Commit this file so every checkout shares one vault.\n{}\n",
+            entry
+        )
+    }
+}
+
+pub fn project_identity(project_path: &Path) -> ObscuraResult<ProjectIdentity> {
+    let canonical_path = project_path
+        .canonicalize()
+        .map_err(|_| ObscuraError::FilePermissionError)?;
+
+    match read_marker(&canonical_path)? {
+        Some(Marker::Id(id)) => Ok(ProjectIdentity::Id(id)),
+        Some(Marker::GitRemote(remote)) => {
+            let url = git_remote_url(&canonical_path, &remote)?;
+            Ok(ProjectIdentity::GitRemote(normalize_remote_url(&url)))
+        }
+        None => Ok(ProjectIdentity::Path(canonical_path)),
+    }
+}
+
+// With an in-repo vault `.obscura` becomes a directory and the marker moves
+// to `.obscura/project`.
+pub fn marker_path(project_path: &Path) -> PathBuf {
+    let marker = project_path.join(MARKER_FILE);
+    if marker.is_dir() {
+        marker.join(REPO_MARKER_FILE)
+    } else {
+        marker
+    }
+}
+
+// Turns a `.obscura` marker file into the `.obscura/` directory used by
+// in-repo vaults, keeping the marker as `.obscura/project`.
+pub fn ensure_repo_dir(project_path: &Path, environment: Option<&str>) -> ObscuraResult<()> {
+    let dir = project_path.join(MARKER_FILE);
+    if dir.is_file() {
+        let marker = fs::read(&dir).map_err(|_| ObscuraError::FilePermissionError)?;
+        fs::remove_file(&dir).map_err(|_| ObscuraError::FilePermissionError)?;
+        fs::create_dir(&dir).map_err(|_| ObscuraError::FilePermissionError)?;
+        fs::write(dir.join(REPO_MARKER_FILE), marker)
+            .map_err(|_| ObscuraError::FilePermissionError)?;
+    }
+    let vault_dir = match environment {
+        Some(_) => dir.join(REPO_ENVS_DIR),
+        None => dir,
+    };
+    fs::create_dir_all(vault_dir).map_err(|_| ObscuraError::FilePermissionError)
+}
+
+pub fn repo_vault_path(project_path: &Path, environment: Option<&str>) -> PathBuf {
+    match environment {
+        Some(environment) => repo_envs_dir(project_path).join(format!("{}.enc", environment)),
+        None => project_path.join(MARKER_FILE).join(REPO_VAULT_FILE),
+    }
+}
+
+pub fn repo_envs_dir(project_path: &Path) -> PathBuf {
+    project_path.join(MARKER_FILE).join(REPO_ENVS_DIR)
+}
+
+// The marker holds one `id = ...` or `git-remote = REMOTE` entry; blank lines
+// and `#` comments are ignored.
+pub fn read_marker(project_path: &Path) -> ObscuraResult<Option<Marker>> {
+    let path = marker_path(project_path);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = fs::read_to_string(&path).map_err(|_| ObscuraError::FilePermissionError)?;
+    let invalid = |reason: &str| {
+        ObscuraError::CustomError(format!("Invalid marker {}: {}", path.display(), reason))
+    };
+
+    let mut marker = None;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| invalid(&format!("expected 'key = value', got '{}'", line)))?;
+        let value = value.trim().trim_matches('"').to_string();
+        if value.is_empty() {
+            return Err(invalid(&format!("'{}' has no value", key.trim())));
+        }
+        let entry = match key.trim() {
+            "id" => Marker::Id(value),
+            "git-remote" => Marker::GitRemote(value),
+            other => return Err(invalid(&format!("unknown key '{}'", other))),
+        };
+        if marker.replace(entry).is_some() {
+            return Err(invalid("only one of 'id' or 'git-remote' may be set"));
+        }
+    }
+
+    marker
+        .map(Some)
+        .ok_or_else(|| invalid("missing 'id' or 'git-remote'"))
+}
+
+pub fn write_marker(project_path: &Path, marker: &Marker) -> ObscuraResult<()> {
+    fs::write(marker_path(project_path), marker.render())
+        .map_err(|_| ObscuraError::FilePermissionError)
+}
+
+fn git_remote_url(project_path: &Path, remote: &str) -> ObscuraResult<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(project_path)
+        .args(["config", "--get", &format!("remote.{}.url", remote)])
+        .output()
+        .map_err(|_| {
+            ObscuraError::CustomError("git is required for git-remote project identity".to_string())
+        })?;
+
+    let url = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    if !output.status.success() || url.is_empty() {
+        return Err(ObscuraError::CustomError(format!(
+            "No git remote '{}' found for {}",
+            remote,
+            project_path.display()
+        )));
+    }
+    Ok(url)
+}
+
+// `https://github.com/Org/Repo.git`, `git@github.com:Org/Repo` and
+// `ssh://git@github.com/Org/Repo/` all normalize to `github.com/Org/Repo`.
+pub fn normalize_remote_url(url: &str) -> String {
+    let url = url.trim();
+    let (rest, scp_like) = match url.split_once("://") {
+        Some((_, rest)) => (rest, false),
+        None => (url, true),
+    };
+    let rest = match rest.split_once('@') {
+        Some((user, host)) if !user.contains('/') => host,
+        _ => rest,
+    };
+
+    let (host, path) = if scp_like {
+        rest.split_once(':').unwrap_or(("", rest))
+    } else {
+        rest.split_once('/').unwrap_or((rest, ""))
+    };
+    // Drop an explicit port so https and ssh remotes of one repo match.
+    let host = host.split(':').next().unwrap_or(host).to_ascii_lowercase();
+    let path = path.trim_matches('/');
+    let path = path.strip_suffix(".git").unwrap_or(path);
+
+    if host.is_empty() {
+        path.to_string()
+    } else {
+        format!("{}/{}", host, path)
+    }
+}
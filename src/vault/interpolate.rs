@@ -0,0 +1,93 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::manager::VaultType;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Literal(String),
+    Reference(Reference),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Reference {
+    pub scope: Option<VaultType>,
+    pub alias: String,
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.scope {
+            Some(VaultType::Global) => write!(f, "${{global:{}}}", self.alias),
+            Some(VaultType::Project) => write!(f, "${{project:{}}}", self.alias),
+            None => write!(f, "${{{}}}", self.alias),
+        }
+    }
+}
+
+pub fn has_references(value: &str) -> bool {
+    value.contains("${")
+}
+
+// `${NAME}` refers to the vault holding the value, `${global:NAME}` and
+// `${project:NAME}` pick a vault explicitly and `$${` is a literal `${`.
+pub fn parse_template(template: &str) -> ObscuraResult<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find('$') {
+        literal.push_str(&rest[..start]);
+        let tail = &rest[start..];
+
+        if let Some(after) = tail.strip_prefix("$${") {
+            literal.push_str("${");
+            rest = after;
+        } else if let Some(body) = tail.strip_prefix("${") {
+            let end = body.find('}').ok_or_else(|| {
+                ObscuraError::CustomError(format!("Unterminated reference in '{}'", template))
+            })?;
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Reference(parse_reference(&body[..end])?));
+            rest = &body[end + 1..];
+        } else {
+            literal.push('$');
+            rest = &tail[1..];
+        }
+    }
+
+    literal.push_str(rest);
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+
+    Ok(segments)
+}
+
+fn parse_reference(body: &str) -> ObscuraResult<Reference> {
+    let (scope, alias) = match body.split_once(':') {
+        Some(("global", alias)) => (Some(VaultType::Global), alias),
+        Some(("project", alias)) => (Some(VaultType::Project), alias),
+        Some((scope, _)) => {
+            return Err(ObscuraError::CustomError(format!(
+                "Unknown vault '{}' in reference '${{{}}}' (expected 'global' or 'project')",
+                scope, body
+            )))
+        }
+        None => (None, body),
+    };
+
+    let alias = alias.trim();
+    if alias.is_empty() {
+        return Err(ObscuraError::CustomError(format!(
+            "Empty reference '${{{}}}'",
+            body
+        )));
+    }
+
+    Ok(Reference {
+        scope,
+        alias: alias.to_string(),
+    })
+}
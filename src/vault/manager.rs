@@ -1,13 +1,49 @@
 use crate::util::errors::{ObscuraError, ObscuraResult};
 use crate::util::paths::{
-    ensure_config_dir, ensure_projects_dir, get_global_vault_path, get_project_meta_path,
-    get_project_vault_path,
+    ensure_config_dir, ensure_projects_dir, get_global_vault_path, get_project_dir,
+    get_project_meta_path, get_project_vault_path, get_projects_dir,
 };
+use crate::vault::identity::{project_identity, repo_vault_path};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+static ENVIRONMENT: OnceLock<Option<String>> = OnceLock::new();
+static SKIPPED_DIRS: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+// Selects the named environment (`--env` / OBSCURA_ENV) used for project
+// vaults. The global vault is shared by all environments.
+pub fn set_environment(environment: Option<String>) -> ObscuraResult<()> {
+    if let Some(environment) = &environment {
+        validate_environment(environment)?;
+    }
+    let _ = ENVIRONMENT.set(environment);
+    Ok(())
+}
+
+pub fn current_environment() -> Option<&'static str> {
+    ENVIRONMENT
+        .get()
+        .and_then(|environment| environment.as_deref())
+}
+
+fn validate_environment(environment: &str) -> ObscuraResult<()> {
+    if environment.is_empty()
+        || !environment
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(ObscuraError::CustomError(format!(
+            "Invalid environment name '{}': use letters, digits, '-' and '_'",
+            environment
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum VaultType {
     Global,
     Project,
@@ -17,6 +53,9 @@ pub enum VaultType {
 pub struct VaultInfo {
     pub vault_type: VaultType,
     pub path: PathBuf,
+    pub project_dir: Option<PathBuf>,
+    pub environment: Option<String>,
+    pub in_repo: bool,
 }
 
 impl VaultInfo {
@@ -24,15 +63,37 @@ impl VaultInfo {
         Ok(Self {
             vault_type: VaultType::Global,
             path: get_global_vault_path()?,
+            project_dir: None,
+            environment: None,
+            in_repo: false,
         })
     }
 
+    // A vault committed inside the project takes precedence over the one in
+    // the config directory.
     pub fn project(project_path: &Path) -> ObscuraResult<Self> {
+        let repo_vault = Self::repo(project_path);
+        if repo_vault.path.exists() {
+            return Ok(repo_vault);
+        }
         Ok(Self {
             vault_type: VaultType::Project,
-            path: get_project_vault_path(project_path)?,
+            path: get_project_vault_path(project_path, current_environment())?,
+            project_dir: project_path.canonicalize().ok(),
+            environment: current_environment().map(str::to_string),
+            in_repo: false,
         })
     }
+
+    pub fn repo(project_path: &Path) -> Self {
+        Self {
+            vault_type: VaultType::Project,
+            path: repo_vault_path(project_path, current_environment()),
+            project_dir: project_path.canonicalize().ok(),
+            environment: current_environment().map(str::to_string),
+            in_repo: true,
+        }
+    }
 }
 
 pub struct VaultManager;
@@ -47,20 +108,78 @@ impl VaultManager {
             return VaultInfo::global();
         }
 
+        let current_dir = std::env::current_dir().map_err(|_| ObscuraError::FilePermissionError)?;
+
+        if let Some(project_vault) = Self::find_project_vault(&current_dir)? {
+            return Ok(project_vault);
+        }
+
         if force_project {
-            let current_dir =
-                std::env::current_dir().map_err(|_| ObscuraError::FilePermissionError)?;
             return VaultInfo::project(&current_dir);
         }
 
-        let current_dir = std::env::current_dir().map_err(|_| ObscuraError::FilePermissionError)?;
+        VaultInfo::global()
+    }
 
-        let project_vault = VaultInfo::project(&current_dir)?;
-        if project_vault.path.exists() {
-            return Ok(project_vault);
+    // Looks for an initialized project in `start` and the directories
+    // `search_dirs` allows above it.
+    pub fn find_project_vault(start: &Path) -> ObscuraResult<Option<VaultInfo>> {
+        let start = start
+            .canonicalize()
+            .map_err(|_| ObscuraError::FilePermissionError)?;
+
+        for dir in search_dirs(&start) {
+            let project_vault = match searched_project(dir) {
+                Some(project_vault) => project_vault,
+                None => continue,
+            };
+            if project_vault.path.exists() {
+                return Ok(Some(project_vault));
+            }
+            // Never fall back to another vault when the selected environment is missing.
+            if let Some(environment) = &project_vault.environment {
+                if get_project_vault_path(dir, None)?.exists()
+                    || repo_vault_path(dir, None).exists()
+                {
+                    return Err(ObscuraError::CustomError(format!(
+                        "Environment '{}' is not initialized for {}; run 'obscura init --env {}'",
+                        environment,
+                        dir.display(),
+                        environment
+                    )));
+                }
+            }
         }
 
-        VaultInfo::global()
+        Ok(None)
+    }
+
+    // Existing vaults in lookup order: the project vaults of the current
+    // directory and its ancestors (nearest first, within `search_dirs`), then
+    // the global vault.
+    pub fn resolve_layers() -> ObscuraResult<Vec<VaultInfo>> {
+        let current_dir = std::env::current_dir()
+            .and_then(|dir| dir.canonicalize())
+            .map_err(|_| ObscuraError::FilePermissionError)?;
+
+        let mut layers = Vec::new();
+        for dir in search_dirs(&current_dir) {
+            if let Some(project_vault) = searched_project(dir) {
+                if project_vault.path.exists() {
+                    layers.push(project_vault);
+                }
+            }
+        }
+
+        let global_vault = VaultInfo::global()?;
+        if global_vault.path.exists() {
+            layers.push(global_vault);
+        }
+
+        if layers.is_empty() {
+            return Err(ObscuraError::VaultNotFound);
+        }
+        Ok(layers)
     }
 
     pub fn ensure_global_vault() -> ObscuraResult<()> {
@@ -70,34 +189,215 @@ impl VaultManager {
 
     pub fn ensure_project_vault(project_path: &Path) -> ObscuraResult<()> {
         ensure_projects_dir()?;
-        let vault_path = get_project_vault_path(project_path)?;
+        let vault_path = VaultInfo::project(project_path)?.path;
         if let Some(parent) = vault_path.parent() {
             std::fs::create_dir_all(parent).map_err(|_| ObscuraError::FilePermissionError)?;
         }
         let meta_path = get_project_meta_path(project_path)?;
-        let meta = ProjectMeta {
-            path: project_path
-                .canonicalize()
-                .map_err(|_| ObscuraError::FilePermissionError)?
-                .to_string_lossy()
-                .to_string(),
-            created_at: chrono::Utc::now().to_rfc3339(),
-            last_used: chrono::Utc::now().to_rfc3339(),
+        ProjectMeta::refresh(ProjectMeta::read(&meta_path), project_path)?.write(&meta_path)
+    }
+
+    // Protection is recorded per user in the config directory, for in-repo
+    // environments as well, so it is looked up by project like `set_protected`.
+    pub fn is_protected(vault_info: &VaultInfo) -> bool {
+        match (&vault_info.environment, &vault_info.project_dir) {
+            (Some(environment), Some(project_dir)) => get_project_meta_path(project_dir)
+                .ok()
+                .and_then(|meta_path| ProjectMeta::read(&meta_path))
+                .is_some_and(|meta| meta.protected_environments.contains(environment)),
+            _ => false,
+        }
+    }
+
+    pub fn set_protected(
+        project_path: &Path,
+        environment: &str,
+        protected: bool,
+    ) -> ObscuraResult<()> {
+        validate_environment(environment)?;
+        if protected
+            && !get_project_vault_path(project_path, Some(environment))?.exists()
+            && !repo_vault_path(project_path, Some(environment)).exists()
+        {
+            return Err(ObscuraError::CustomError(format!(
+                "Environment '{}' is not initialized for {}; run 'obscura init --env {}'",
+                environment,
+                project_path.display(),
+                environment
+            )));
+        }
+        let meta_path = get_project_meta_path(project_path)?;
+        // Projects with only in-repo vaults get a record for their protection.
+        let mut meta = match ProjectMeta::read(&meta_path) {
+            Some(meta) => meta,
+            None => {
+                if let Some(parent) = meta_path.parent() {
+                    fs::create_dir_all(parent).map_err(|_| ObscuraError::FilePermissionError)?;
+                }
+                ProjectMeta::refresh(None, project_path)?
+            }
         };
-        let meta_json =
-            serde_json::to_string_pretty(&meta).map_err(|_| ObscuraError::FilePermissionError)?;
-        std::fs::write(&meta_path, meta_json).map_err(|_| ObscuraError::FilePermissionError)?;
+        meta.protected_environments
+            .retain(|name| name != environment);
+        if protected {
+            meta.protected_environments.push(environment.to_string());
+            meta.protected_environments.sort();
+        }
+        meta.write(&meta_path)
+    }
+
+    // Moves the vault stored under `from_key` to the key `project_path` now
+    // resolves to, keeping its creation time.
+    pub fn attach_project_vault(from_key: &str, project_path: &Path) -> ObscuraResult<VaultInfo> {
+        let identity = project_identity(project_path)?;
+        let from_dir = get_project_dir(from_key)?;
+        let to_dir = get_project_dir(&identity.key())?;
+
+        if project_vault_files(&from_dir).is_empty() {
+            return Err(ObscuraError::VaultNotFound);
+        }
+        let mut protected_environments = Vec::new();
+        if from_dir != to_dir {
+            if !project_vault_files(&to_dir).is_empty() {
+                return Err(ObscuraError::CustomError(format!(
+                    "A vault or environment vault already exists for {}; delete it first",
+                    identity
+                )));
+            }
+            // Only metadata is left there; its protected environments carry over.
+            if to_dir.exists() {
+                if let Some(meta) = ProjectMeta::read(&to_dir.join("meta.json")) {
+                    protected_environments = meta.protected_environments;
+                }
+                fs::remove_dir_all(&to_dir).map_err(|_| ObscuraError::FilePermissionError)?;
+            }
+            fs::rename(&from_dir, &to_dir).map_err(|_| ObscuraError::FilePermissionError)?;
+        }
+
+        let meta_path = to_dir.join("meta.json");
+        let mut meta = ProjectMeta::refresh(ProjectMeta::read(&meta_path), project_path)?;
+        for environment in protected_environments {
+            if !meta.protected_environments.contains(&environment) {
+                meta.protected_environments.push(environment);
+            }
+        }
+        meta.write(&meta_path)?;
+
+        VaultInfo::project(project_path)
+    }
+
+    // Every directory under `projects/`, whether or not its metadata is readable.
+    pub fn list_projects() -> ObscuraResult<Vec<ProjectRecord>> {
+        let projects_dir = get_projects_dir()?;
+        if !projects_dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut records = Vec::new();
+        for entry in fs::read_dir(&projects_dir).map_err(|_| ObscuraError::FilePermissionError)? {
+            let dir = entry.map_err(|_| ObscuraError::FilePermissionError)?.path();
+            if !dir.is_dir() {
+                continue;
+            }
+            records.push(ProjectRecord {
+                key: dir
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                meta: ProjectMeta::read(&dir.join("meta.json")),
+                dir,
+            });
+        }
+        records.sort_by(|a, b| a.path().cmp(&b.path()).then(a.key.cmp(&b.key)));
+        Ok(records)
+    }
+
+    // Records that a project vault was just used, and from which directory:
+    // a vault identified by a marker follows its project when it moves.
+    // Bookkeeping only, so failures are ignored.
+    pub fn touch_project(vault_path: &Path) {
+        let meta_path = match project_meta_path(vault_path) {
+            Some(meta_path) => meta_path,
+            None => return,
+        };
+        if let Some(mut meta) = ProjectMeta::read(&meta_path) {
+            meta.last_used = chrono::Utc::now().to_rfc3339();
+            if let Some(project_dir) = using_project_dir(vault_path) {
+                meta.path = project_dir.to_string_lossy().to_string();
+            }
+            let _ = meta.write(&meta_path);
+        }
+    }
+
+    pub fn remove_project(record: &ProjectRecord) -> ObscuraResult<()> {
+        fs::remove_dir_all(&record.dir).map_err(|_| ObscuraError::FilePermissionError)
+    }
+
+    // Removes a config-directory vault that moved elsewhere, dropping the
+    // project record once no default or environment vault and no protection
+    // remains.
+    pub fn retire_project_vault(vault_path: &Path) -> ObscuraResult<()> {
+        Self::remove_vault_file(vault_path)?;
+        let meta_path = match project_meta_path(vault_path) {
+            Some(meta_path) => meta_path,
+            None => return Ok(()),
+        };
+        let project_dir = match meta_path.parent() {
+            Some(project_dir) => project_dir,
+            None => return Ok(()),
+        };
+        let record = ProjectRecord {
+            key: String::new(),
+            dir: project_dir.to_path_buf(),
+            meta: ProjectMeta::read(&meta_path),
+        };
+        // Protection of in-repo environments is also recorded here.
+        let protects = record
+            .meta
+            .as_ref()
+            .is_some_and(|meta| !meta.protected_environments.is_empty());
+        if !record.has_vaults() && !protects {
+            fs::remove_dir_all(project_dir).map_err(|_| ObscuraError::FilePermissionError)?;
+        }
         Ok(())
     }
 
     pub fn delete_vault(vault_info: &VaultInfo) -> ObscuraResult<()> {
-        match vault_info.vault_type {
-            VaultType::Global => Self::delete_global_vault(&vault_info.path),
-            VaultType::Project => Self::delete_project_vault(&vault_info.path),
+        match (vault_info.vault_type, &vault_info.environment) {
+            (VaultType::Global, _) => Self::remove_vault_file(&vault_info.path),
+            // In-repo vaults leave the rest of `.obscura/` alone.
+            (VaultType::Project, _) if vault_info.in_repo => {
+                Self::remove_vault_file(&vault_info.path)
+            }
+            (VaultType::Project, Some(environment)) => {
+                Self::delete_environment_vault(&vault_info.path, environment)
+            }
+            (VaultType::Project, None) => Self::delete_project_vault(&vault_info.path),
+        }
+    }
+
+    // Environments share the project directory with the default vault, so
+    // only the environment file and its protection go.
+    fn delete_environment_vault(path: &Path, environment: &str) -> ObscuraResult<()> {
+        if let Some(meta_path) = project_meta_path(path) {
+            if let Some(mut meta) = ProjectMeta::read(&meta_path) {
+                if meta
+                    .protected_environments
+                    .iter()
+                    .any(|name| name == environment)
+                {
+                    meta.protected_environments
+                        .retain(|name| name != environment);
+                    meta.write(&meta_path)?;
+                }
+            }
         }
+        Self::retire_project_vault(path)
     }
 
-    fn delete_global_vault(path: &Path) -> ObscuraResult<()> {
+    // Removes one vault file, global, project or in-repo; a missing file is
+    // not an error.
+    fn remove_vault_file(path: &Path) -> ObscuraResult<()> {
         if path.exists() {
             fs::remove_file(path).map_err(|_| ObscuraError::FilePermissionError)?;
         }
@@ -105,10 +405,15 @@ impl VaultManager {
     }
 
     fn delete_project_vault(path: &Path) -> ObscuraResult<()> {
-        if path.exists() {
-            fs::remove_file(path).map_err(|_| ObscuraError::FilePermissionError)?;
-        }
+        Self::remove_vault_file(path)?;
 
+        // The project record stays while environment vaults remain.
+        if path
+            .parent()
+            .is_some_and(|parent| parent.join("envs").exists())
+        {
+            return Self::retire_project_vault(path);
+        }
         if let Some(parent) = path.parent() {
             if parent.exists() {
                 fs::remove_dir_all(parent).map_err(|_| ObscuraError::FilePermissionError)?;
@@ -119,9 +424,210 @@ impl VaultManager {
     }
 }
 
+pub const NO_PARENT_SEARCH_ENV: &str = "OBSCURA_NO_PARENT_SEARCH";
+
+pub fn parent_search_enabled() -> bool {
+    match std::env::var(NO_PARENT_SEARCH_ENV) {
+        Ok(value) => matches!(value.trim(), "" | "0" | "false"),
+        Err(_) => true,
+    }
+}
+
+// `start` and its ancestors up to and including the git root or $HOME, or
+// only `start` when OBSCURA_NO_PARENT_SEARCH is set. `start` must be canonical.
+pub(crate) fn search_dirs(start: &Path) -> Vec<&Path> {
+    let home = directories::BaseDirs::new().and_then(|dirs| dirs.home_dir().canonicalize().ok());
+    let mut dirs = Vec::new();
+    for dir in start.ancestors() {
+        dirs.push(dir);
+        if !parent_search_enabled() || dir.join(".git").exists() || home.as_deref() == Some(dir) {
+            break;
+        }
+    }
+    dirs
+}
+
+// The directory from which the current command reaches `vault_path`.
+fn using_project_dir(vault_path: &Path) -> Option<PathBuf> {
+    let current_dir = std::env::current_dir()
+        .and_then(|dir| dir.canonicalize())
+        .ok()?;
+    search_dirs(&current_dir)
+        .into_iter()
+        .find(|dir| searched_project(dir).is_some_and(|info| info.path == vault_path))
+        .map(Path::to_path_buf)
+}
+
+// The project vault of a directory met while searching. A directory whose
+// identity cannot be determined, e.g. because of a malformed `.obscura`
+// marker, is skipped with a warning so it only breaks commands that need it.
+fn searched_project(dir: &Path) -> Option<VaultInfo> {
+    match VaultInfo::project(dir) {
+        Ok(project_vault) => Some(project_vault),
+        Err(err) => {
+            let mut skipped = SKIPPED_DIRS.lock().unwrap_or_else(|err| err.into_inner());
+            if !skipped.iter().any(|skipped_dir| skipped_dir == dir) {
+                eprintln!("Warning: ignoring project at {}: {}", dir.display(), err);
+                skipped.push(dir.to_path_buf());
+            }
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct ProjectMeta {
-    path: String,
-    created_at: String,
-    last_used: String,
+pub struct ProjectMeta {
+    pub path: String,
+    pub created_at: String,
+    pub last_used: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub protected_environments: Vec<String>,
+}
+
+impl ProjectMeta {
+    // Points the metadata at `project_path`, keeping what is known about the vault.
+    fn refresh(existing: Option<Self>, project_path: &Path) -> ObscuraResult<Self> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let (created_at, protected_environments) = match existing {
+            Some(meta) => (meta.created_at, meta.protected_environments),
+            None => (now.clone(), Vec::new()),
+        };
+        Ok(Self {
+            path: project_path
+                .canonicalize()
+                .map_err(|_| ObscuraError::FilePermissionError)?
+                .to_string_lossy()
+                .to_string(),
+            created_at,
+            last_used: now,
+            identity: Some(project_identity(project_path)?.to_string()),
+            protected_environments,
+        })
+    }
+
+    fn read(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn write(&self, path: &Path) -> ObscuraResult<()> {
+        let meta_json =
+            serde_json::to_string_pretty(self).map_err(|_| ObscuraError::FilePermissionError)?;
+        fs::write(path, meta_json).map_err(|_| ObscuraError::FilePermissionError)
+    }
+}
+
+// The default vault and the environment vaults stored in a project's
+// directory under `projects/`.
+pub fn project_vault_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir.join("envs"))
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "enc"))
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    let default = dir.join("vault.enc");
+    if default.exists() {
+        files.insert(0, default);
+    }
+    files
+}
+
+pub struct ProjectRecord {
+    pub key: String,
+    pub dir: PathBuf,
+    pub meta: Option<ProjectMeta>,
+}
+
+impl ProjectRecord {
+    pub fn vault_path(&self) -> PathBuf {
+        self.dir.join("vault.enc")
+    }
+
+    pub fn vault_files(&self) -> Vec<PathBuf> {
+        project_vault_files(&self.dir)
+    }
+
+    // The default vault or at least one environment vault exists.
+    pub fn has_vaults(&self) -> bool {
+        !self.vault_files().is_empty()
+    }
+
+    pub fn environments(&self) -> Vec<String> {
+        let mut environments: Vec<String> = fs::read_dir(self.dir.join("envs"))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|entry| {
+                        entry
+                            .file_name()
+                            .to_string_lossy()
+                            .strip_suffix(".enc")
+                            .map(str::to_string)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        environments.sort();
+        environments
+    }
+
+    pub fn path(&self) -> Option<&str> {
+        self.meta.as_ref().map(|meta| meta.path.as_str())
+    }
+
+    // Only a path-keyed vault whose directory is gone is certainly orphaned.
+    // Vaults identified by a marker may still be used by another checkout,
+    // and without metadata nothing is known about the project.
+    pub fn status(&self) -> ProjectStatus {
+        let meta = match &self.meta {
+            Some(meta) => meta,
+            None => return ProjectStatus::Unknown,
+        };
+        if Path::new(&meta.path).is_dir() {
+            return ProjectStatus::Active;
+        }
+        match meta.identity.as_deref() {
+            Some(identity) if !identity.starts_with("path:") => ProjectStatus::Unknown,
+            _ => ProjectStatus::Orphan,
+        }
+    }
+
+    pub fn is_orphan(&self) -> bool {
+        self.status() == ProjectStatus::Orphan
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectStatus {
+    Active,
+    Orphan,
+    Unknown,
+}
+
+impl ProjectStatus {
+    pub fn label(self) -> &'static str {
+        match self {
+            ProjectStatus::Active => "active",
+            ProjectStatus::Orphan => "orphan",
+            ProjectStatus::Unknown => "unknown",
+        }
+    }
+}
+
+// `meta.json` of the project owning a project or environment vault file.
+fn project_meta_path(vault_path: &Path) -> Option<PathBuf> {
+    let parent = vault_path.parent()?;
+    let project_dir = if parent.file_name().is_some_and(|name| name == "envs") {
+        parent.parent()?
+    } else {
+        parent
+    };
+    Some(project_dir.join("meta.json"))
 }
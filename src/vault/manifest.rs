@@ -0,0 +1,118 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use crate::vault::manager::{search_dirs, VaultType};
+use crate::vault::namespace::SEPARATOR;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const MANIFEST_FILE: &str = "obscura.toml";
+
+// The secrets a project needs, committed next to its code:
+//
+//   [secrets.DATABASE_URL]
+//   description = "Postgres connection string"
+//   max_age_days = 90
+//
+//   [secrets."stripe/SECRET_KEY"]
+//   env = "STRIPE_KEY"
+//   vault = "global"
+//   required = false
+pub struct Manifest {
+    pub path: PathBuf,
+    pub secrets: BTreeMap<String, SecretSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestFile {
+    #[serde(default)]
+    secrets: BTreeMap<String, SecretSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecretSpec {
+    #[serde(default = "default_required")]
+    pub required: bool,
+    pub env: Option<String>,
+    pub description: Option<String>,
+    pub vault: Option<VaultType>,
+    pub max_age_days: Option<u64>,
+}
+
+fn default_required() -> bool {
+    true
+}
+
+impl SecretSpec {
+    // Namespaced aliases are injected under their leaf name unless `env` says otherwise.
+    pub fn env_name(&self, alias: &str) -> String {
+        match &self.env {
+            Some(env) => env.clone(),
+            None => alias.rsplit(SEPARATOR).next().unwrap_or(alias).to_string(),
+        }
+    }
+
+    pub fn max_age_seconds(&self) -> Option<i64> {
+        self.max_age_days
+            .and_then(|days| i64::try_from(days).ok()?.checked_mul(86_400))
+    }
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> ObscuraResult<Self> {
+        let content = fs::read_to_string(path).map_err(|_| ObscuraError::FilePermissionError)?;
+        let manifest: ManifestFile = toml::from_str(&content).map_err(|err| {
+            ObscuraError::CustomError(format!(
+                "Invalid manifest {}: {}",
+                path.display(),
+                err.message()
+            ))
+        })?;
+
+        for (alias, spec) in &manifest.secrets {
+            if spec.env_name(alias).is_empty() {
+                return Err(ObscuraError::CustomError(format!(
+                    "Invalid manifest {}: '{}' has an empty env name",
+                    path.display(),
+                    alias
+                )));
+            }
+            if spec.max_age_days.is_some() && spec.max_age_seconds().is_none() {
+                return Err(ObscuraError::CustomError(format!(
+                    "Invalid manifest {}: max_age_days of '{}' is too large",
+                    path.display(),
+                    alias
+                )));
+            }
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            secrets: manifest.secrets,
+        })
+    }
+
+    // The nearest manifest in `start` or its parents, searched like project
+    // vaults: up to the git root or $HOME.
+    pub fn find(start: &Path) -> ObscuraResult<Option<Self>> {
+        let start = start
+            .canonicalize()
+            .map_err(|_| ObscuraError::FilePermissionError)?;
+
+        for dir in search_dirs(&start) {
+            let path = dir.join(MANIFEST_FILE);
+            if path.is_file() {
+                return Self::load(&path).map(Some);
+            }
+        }
+
+        Ok(None)
+    }
+
+    pub fn find_current() -> ObscuraResult<Option<Self>> {
+        let current_dir = std::env::current_dir().map_err(|_| ObscuraError::FilePermissionError)?;
+        Self::find(&current_dir)
+    }
+}
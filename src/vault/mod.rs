@@ -1,3 +1,8 @@
 pub mod file;
+pub mod identity;
+pub mod interpolate;
 pub mod manager;
+pub mod manifest;
 pub mod model;
+pub mod namespace;
+pub mod template;
@@ -4,14 +4,6 @@ use base64::{engine::general_purpose, Engine as _};
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VaultHeader {
-    pub version: u32,
-    pub created_at: String,
-    pub kdf: KdfParams,
-    pub dek_wrapped: EncryptedData,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KdfParams {
     pub alg: String,
@@ -44,12 +36,52 @@ pub struct VaultFile {
     pub created_at: String,
     pub kdf: KdfParams,
     pub dek_wrapped: EncryptedData,
-    pub body: VaultBody,
+    #[serde(flatten)]
+    pub storage: VaultStorage,
+}
+
+// In-repo vaults keep every alias as its own entry, encrypted individually,
+// so unchanged secrets serialize identically between saves. Entries are keyed
+// by a keyed hash of the alias name and hide the name and metadata; the MAC
+// covers the revision and the whole entry set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum VaultStorage {
+    Sealed {
+        body: VaultBody,
+    },
+    Entries {
+        revision: u64,
+        mac: String,
+        entries: std::collections::BTreeMap<String, EncryptedData>,
+    },
+    // The first in-repo format, with names and metadata in the clear. It is
+    // still read, and saving upgrades it to `Entries`.
+    LegacyEntries {
+        aliases: std::collections::BTreeMap<String, SealedAlias>,
+    },
+}
+
+impl VaultStorage {
+    pub fn empty_entries() -> Self {
+        VaultStorage::Entries {
+            revision: 0,
+            mac: String::new(),
+            entries: std::collections::BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedAlias {
+    #[serde(flatten)]
+    pub data: AliasData,
+    pub mac: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AliasesData {
-    pub aliases: std::collections::HashMap<String, AliasData>,
+    pub aliases: std::collections::BTreeMap<String, AliasData>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -57,10 +89,92 @@ pub struct AliasData {
     pub value_enc: EncryptedData,
     pub created_at: String,
     pub rotated_at: Option<String>,
+    #[serde(default, skip_serializing_if = "AliasKind::is_text")]
+    pub kind: AliasKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub otp: Option<OtpParams>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    // Only values added with `--interpolate` have `${...}` references expanded.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub interpolate: bool,
+}
+
+impl AliasData {
+    pub fn reencrypt(
+        &self,
+        from: &crate::crypto::aead::AeadKey,
+        to: &crate::crypto::aead::AeadKey,
+    ) -> ObscuraResult<Self> {
+        let value = decrypt_value(&self.value_enc, from)?;
+        Ok(Self {
+            value_enc: encrypt_value(&value, to)?,
+            ..self.clone()
+        })
+    }
+
+    // Plaintext length derived from the ciphertext, minus the 16-byte Poly1305 tag.
+    pub fn value_len(&self) -> Option<usize> {
+        general_purpose::STANDARD
+            .decode(&self.value_enc.ciphertext_b64)
+            .ok()
+            .and_then(|ciphertext| ciphertext.len().checked_sub(16))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AliasKind {
+    #[default]
+    Text,
+    File,
+    Otp,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtpParams {
+    pub method: OtpMethod,
+    pub algorithm: OtpAlgorithm,
+    pub digits: u32,
+    pub period: u64,
+    pub counter: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpMethod {
+    Totp,
+    Hotp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OtpAlgorithm {
+    Sha1,
+    Sha256,
+    Sha512,
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
+}
+
+impl AliasKind {
+    pub fn is_text(&self) -> bool {
+        *self == AliasKind::Text
+    }
 }
 
 impl VaultFile {
-    pub fn new(kdf_params: CryptoKdfParams, dek_wrapped: EncryptedData, body: VaultBody) -> Self {
+    pub fn new(
+        kdf_params: CryptoKdfParams,
+        dek_wrapped: EncryptedData,
+        storage: VaultStorage,
+    ) -> Self {
         Self {
             version: 1,
             created_at: Utc::now().to_rfc3339(),
@@ -74,7 +188,7 @@ impl VaultFile {
                 },
             },
             dek_wrapped,
-            body,
+            storage,
         }
     }
 }
@@ -99,7 +213,7 @@ impl From<KdfParams> for crate::crypto::kdf::KdfParams {
             .decode(&params.salt_b64)
             .ok()
             .and_then(|bytes| bytes.try_into().ok())
-            .unwrap_or_else(|| [0u8; 16]);
+            .unwrap_or([0u8; 16]);
 
         Self {
             salt,
@@ -113,7 +227,7 @@ impl From<KdfParams> for crate::crypto::kdf::KdfParams {
 impl AliasesData {
     pub fn new() -> Self {
         Self {
-            aliases: std::collections::HashMap::new(),
+            aliases: std::collections::BTreeMap::new(),
         }
     }
 
@@ -123,21 +237,62 @@ impl AliasesData {
         value: String,
         dek: &crate::crypto::aead::AeadKey,
     ) -> ObscuraResult<()> {
-        let value_enc = encrypt_value(&value, dek)?;
+        self.add_alias_bytes(alias, value.as_bytes(), AliasKind::Text, dek)
+    }
+
+    pub fn add_alias_bytes(
+        &mut self,
+        alias: String,
+        value: &[u8],
+        kind: AliasKind,
+        dek: &crate::crypto::aead::AeadKey,
+    ) -> ObscuraResult<()> {
+        let value_enc = encrypt_value(value, dek)?;
         let alias_data = AliasData {
             value_enc,
             created_at: Utc::now().to_rfc3339(),
             rotated_at: None,
+            kind,
+            otp: None,
+            tags: Vec::new(),
+            interpolate: false,
         };
         self.aliases.insert(alias, alias_data);
         Ok(())
     }
 
+    pub fn add_otp_alias(
+        &mut self,
+        alias: String,
+        secret: String,
+        params: OtpParams,
+        dek: &crate::crypto::aead::AeadKey,
+    ) -> ObscuraResult<()> {
+        self.add_alias_bytes(alias.clone(), secret.as_bytes(), AliasKind::Otp, dek)?;
+        if let Some(alias_data) = self.aliases.get_mut(&alias) {
+            alias_data.otp = Some(params);
+        }
+        Ok(())
+    }
+
     pub fn get_alias(
         &self,
         alias: &str,
         dek: &crate::crypto::aead::AeadKey,
     ) -> ObscuraResult<Option<String>> {
+        match self.get_alias_bytes(alias, dek)? {
+            Some(bytes) => String::from_utf8(bytes)
+                .map(Some)
+                .map_err(|_| ObscuraError::BinaryValue(alias.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_alias_bytes(
+        &self,
+        alias: &str,
+        dek: &crate::crypto::aead::AeadKey,
+    ) -> ObscuraResult<Option<Vec<u8>>> {
         if let Some(alias_data) = self.aliases.get(alias) {
             let value = decrypt_value(&alias_data.value_enc, dek)?;
             Ok(Some(value))
@@ -146,32 +301,104 @@ impl AliasesData {
         }
     }
 
+    pub fn alias_kind(&self, alias: &str) -> Option<AliasKind> {
+        self.aliases.get(alias).map(|alias_data| alias_data.kind)
+    }
+
+    pub fn interpolates(&self, alias: &str) -> bool {
+        self.aliases
+            .get(alias)
+            .is_some_and(|alias_data| alias_data.interpolate)
+    }
+
+    pub fn set_interpolate(&mut self, alias: &str, interpolate: bool) -> bool {
+        if let Some(alias_data) = self.aliases.get_mut(alias) {
+            alias_data.interpolate = interpolate;
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn remove_alias(&mut self, alias: &str) -> bool {
         self.aliases.remove(alias).is_some()
     }
 
-    pub fn rotate_alias(
+    pub fn rotate_alias_bytes(
         &mut self,
         alias: &str,
-        new_value: String,
+        new_value: &[u8],
+        kind: AliasKind,
         dek: &crate::crypto::aead::AeadKey,
     ) -> ObscuraResult<bool> {
         if let Some(alias_data) = self.aliases.get_mut(alias) {
-            alias_data.value_enc = encrypt_value(&new_value, dek)?;
+            alias_data.value_enc = encrypt_value(new_value, dek)?;
             alias_data.rotated_at = Some(Utc::now().to_rfc3339());
+            alias_data.kind = kind;
+            if kind != AliasKind::Otp {
+                alias_data.otp = None;
+            }
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    pub fn rotate_otp_alias(
+        &mut self,
+        alias: &str,
+        secret: String,
+        params: OtpParams,
+        dek: &crate::crypto::aead::AeadKey,
+    ) -> ObscuraResult<bool> {
+        if !self.rotate_alias_bytes(alias, secret.as_bytes(), AliasKind::Otp, dek)? {
+            return Ok(false);
+        }
+        if let Some(alias_data) = self.aliases.get_mut(alias) {
+            alias_data.otp = Some(params);
+        }
+        Ok(true)
+    }
+
+    pub fn set_tags(&mut self, alias: &str, tags: &[String]) -> bool {
+        if let Some(alias_data) = self.aliases.get_mut(alias) {
+            for tag in tags {
+                if !alias_data.tags.contains(tag) {
+                    alias_data.tags.push(tag.clone());
+                }
+            }
+            alias_data.tags.sort();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn remove_tags(&mut self, alias: &str, tags: &[String]) -> bool {
+        if let Some(alias_data) = self.aliases.get_mut(alias) {
+            alias_data.tags.retain(|tag| !tags.contains(tag));
+            true
+        } else {
+            false
+        }
+    }
+
     pub fn list_aliases(&self) -> Vec<String> {
         self.aliases.keys().cloned().collect()
     }
+
+    pub fn list_namespace(&self, namespace: &str) -> Vec<String> {
+        self.aliases
+            .range(namespace.to_string()..)
+            .map(|(alias, _)| alias)
+            .take_while(|alias| crate::vault::namespace::is_in_namespace(alias, namespace))
+            .cloned()
+            .collect()
+    }
 }
 
-fn encrypt_value(value: &str, dek: &crate::crypto::aead::AeadKey) -> ObscuraResult<EncryptedData> {
-    let aead_result = crate::crypto::aead::encrypt_with_key(value.as_bytes(), dek, b"")?;
+fn encrypt_value(value: &[u8], dek: &crate::crypto::aead::AeadKey) -> ObscuraResult<EncryptedData> {
+    let aead_result = crate::crypto::aead::encrypt_with_key(value, dek, b"")?;
     Ok(EncryptedData {
         nonce_b64: general_purpose::STANDARD.encode(aead_result.nonce),
         ciphertext_b64: general_purpose::STANDARD.encode(aead_result.ciphertext),
@@ -181,7 +408,7 @@ fn encrypt_value(value: &str, dek: &crate::crypto::aead::AeadKey) -> ObscuraResu
 fn decrypt_value(
     value_enc: &EncryptedData,
     dek: &crate::crypto::aead::AeadKey,
-) -> ObscuraResult<String> {
+) -> ObscuraResult<Vec<u8>> {
     let nonce = general_purpose::STANDARD
         .decode(&value_enc.nonce_b64)
         .map_err(|_| ObscuraError::DecryptionFailed)?;
@@ -196,6 +423,5 @@ fn decrypt_value(
     let mut nonce_array = [0u8; 24];
     nonce_array.copy_from_slice(&nonce);
 
-    let plaintext = crate::crypto::aead::decrypt_with_key(&ciphertext, dek, &nonce_array, b"")?;
-    String::from_utf8(plaintext).map_err(|_| ObscuraError::DecryptionFailed)
+    crate::crypto::aead::decrypt_with_key(&ciphertext, dek, &nonce_array, b"")
 }
@@ -0,0 +1,72 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+
+pub const SEPARATOR: char = '/';
+
+pub fn validate_alias(alias: &str) -> ObscuraResult<()> {
+    if alias.is_empty()
+        || alias.starts_with(SEPARATOR)
+        || alias.ends_with(SEPARATOR)
+        || alias.split(SEPARATOR).any(|segment| segment.is_empty())
+    {
+        return Err(ObscuraError::CustomError(format!(
+            "Invalid alias '{}': namespaces are separated by single '/' characters",
+            alias
+        )));
+    }
+    Ok(())
+}
+
+// `aws/prod`, `aws/prod/` and `/aws/prod` all name the same namespace.
+pub fn normalize_namespace(namespace: &str) -> String {
+    let trimmed = namespace.trim_matches(SEPARATOR);
+    if trimmed.is_empty() {
+        String::new()
+    } else {
+        format!("{}{}", trimmed, SEPARATOR)
+    }
+}
+
+pub fn is_in_namespace(alias: &str, namespace: &str) -> bool {
+    alias.starts_with(namespace)
+}
+
+pub fn is_namespaced(alias: &str) -> bool {
+    alias.contains(SEPARATOR)
+}
+
+// Path relative to the namespace, with nested separators turned into `_` so
+// the result is usable as an environment variable name.
+pub fn env_name_in_namespace(alias: &str, namespace: &str) -> Option<String> {
+    alias
+        .strip_prefix(namespace)
+        .map(|relative| relative.replace(SEPARATOR, "_"))
+}
+
+pub fn render_tree(aliases: &[String], namespace: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut open: Vec<&str> = Vec::new();
+
+    for alias in aliases {
+        let relative = match alias.strip_prefix(namespace) {
+            Some(relative) => relative,
+            None => continue,
+        };
+        let segments: Vec<&str> = relative.split(SEPARATOR).collect();
+        let (folders, leaf) = segments.split_at(segments.len() - 1);
+
+        let common = open
+            .iter()
+            .zip(folders)
+            .take_while(|(open, folder)| open == folder)
+            .count();
+        open.truncate(common);
+
+        for folder in &folders[common..] {
+            lines.push(format!("{}{}/", "  ".repeat(open.len()), folder));
+            open.push(folder);
+        }
+        lines.push(format!("{}{}", "  ".repeat(open.len()), leaf[0]));
+    }
+
+    lines
+}
@@ -0,0 +1,240 @@
+use crate::util::errors::{ObscuraError, ObscuraResult};
+use base64::{engine::general_purpose, Engine as _};
+use percent_encoding::{percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+// RFC 3986 unreserved characters are left as they are by `url-encode`.
+pub const URL_UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+// `{{ ALIAS }}` placeholders, used in rendered config files and in command
+// arguments. Filters apply left to right:
+//
+//   {{ NPM_TOKEN }}
+//   {{ SERVICE_ACCOUNT | base64 }}
+//   {{ DB_PASSWORD | url-encode }}
+//   {{ LOG_LEVEL | default("info") }}
+//
+// `\{{` is a literal `{{`, and `\\{{` a backslash before a placeholder.
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+struct Placeholder {
+    alias: String,
+    filters: Vec<Filter>,
+    line: usize,
+}
+
+enum Filter {
+    Base64,
+    JsonEscape,
+    UrlEncode,
+    Default(String),
+}
+
+impl Template {
+    pub fn parse(text: &str) -> ObscuraResult<Self> {
+        let mut parts = Vec::new();
+        let mut rest = text;
+
+        while let Some(start) = rest.find("{{") {
+            let before = &rest[..start];
+            if before.ends_with("\\\\") {
+                // `\\{{` is a backslash followed by a placeholder.
+                parts.push(Part::Literal(before[..start - 1].to_string()));
+            } else if let Some(before) = before.strip_suffix('\\') {
+                // `\{{` is a literal `{{`.
+                parts.push(Part::Literal(format!("{}{{{{", before)));
+                rest = &rest[start + 2..];
+                continue;
+            } else if start > 0 {
+                parts.push(Part::Literal(before.to_string()));
+            }
+            let line = line_of(text, text.len() - rest.len() + start);
+            let body = &rest[start + 2..];
+            let (pieces, end) = split_placeholder(body).ok_or_else(|| {
+                ObscuraError::CustomError(format!("Unclosed '{{{{' on line {}", line))
+            })?;
+            parts.push(Part::Placeholder(parse_placeholder(&pieces, line)?));
+            rest = &body[end + 2..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Literal(rest.to_string()));
+        }
+        Ok(Self { parts })
+    }
+
+    // `lookup` returns None for aliases that do not exist, which is an error
+    // unless a `default` filter supplies a value.
+    pub fn render(
+        &self,
+        mut lookup: impl FnMut(&str) -> ObscuraResult<Option<Vec<u8>>>,
+    ) -> ObscuraResult<Vec<u8>> {
+        let mut output = Vec::new();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => output.extend_from_slice(text.as_bytes()),
+                Part::Placeholder(placeholder) => {
+                    let mut value = lookup(&placeholder.alias)?;
+                    for filter in &placeholder.filters {
+                        value = filter.apply(value, placeholder)?;
+                    }
+                    let value = value.ok_or_else(|| {
+                        ObscuraError::CustomError(format!(
+                            "Alias '{}' on line {} not found",
+                            placeholder.alias, placeholder.line
+                        ))
+                    })?;
+                    output.extend_from_slice(&value);
+                }
+            }
+        }
+        Ok(output)
+    }
+}
+
+impl Filter {
+    fn apply(
+        &self,
+        value: Option<Vec<u8>>,
+        placeholder: &Placeholder,
+    ) -> ObscuraResult<Option<Vec<u8>>> {
+        let value = match (self, value) {
+            (Filter::Default(fallback), None) => return Ok(Some(fallback.clone().into_bytes())),
+            (_, None) => return Ok(None),
+            (_, Some(value)) => value,
+        };
+        Ok(Some(match self {
+            Filter::Base64 => general_purpose::STANDARD.encode(&value).into_bytes(),
+            Filter::UrlEncode => percent_encode(&value, URL_UNRESERVED)
+                .to_string()
+                .into_bytes(),
+            Filter::JsonEscape => {
+                let text = String::from_utf8(value).map_err(|_| {
+                    ObscuraError::CustomError(format!(
+                        "Alias '{}' on line {} is not valid UTF-8 and cannot be JSON-escaped",
+                        placeholder.alias, placeholder.line
+                    ))
+                })?;
+                let quoted = serde_json::Value::String(text).to_string();
+                quoted.as_bytes()[1..quoted.len() - 1].to_vec()
+            }
+            Filter::Default(_) => value,
+        }))
+    }
+}
+
+fn line_of(text: &str, offset: usize) -> usize {
+    text[..offset].matches('\n').count() + 1
+}
+
+// Splits the text after `{{` at `|` up to the closing `}}`, ignoring both
+// inside quoted filter arguments. Returns the pieces and the offset of `}}`.
+fn split_placeholder(body: &str) -> Option<(Vec<String>, usize)> {
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    let mut quote = None;
+    let mut escaped = false;
+
+    for (index, c) in body.char_indices() {
+        match quote {
+            Some(_) if escaped => escaped = false,
+            Some(_) if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if body[index..].starts_with("}}") => {
+                pieces.push(piece);
+                return Some((pieces, index));
+            }
+            None if c == '|' => {
+                pieces.push(std::mem::take(&mut piece));
+                continue;
+            }
+            None => {}
+        }
+        piece.push(c);
+    }
+    None
+}
+
+fn parse_placeholder(pieces: &[String], line: usize) -> ObscuraResult<Placeholder> {
+    let alias = pieces[0].trim();
+    if alias.is_empty() || alias.contains(char::is_whitespace) {
+        return Err(ObscuraError::CustomError(format!(
+            "Invalid placeholder '{{{{{}}}}}' on line {}",
+            pieces.join("|"),
+            line
+        )));
+    }
+
+    let filters = pieces[1..]
+        .iter()
+        .map(|piece| parse_filter(piece.trim(), line))
+        .collect::<ObscuraResult<_>>()?;
+
+    Ok(Placeholder {
+        alias: alias.to_string(),
+        filters,
+        line,
+    })
+}
+
+fn parse_filter(text: &str, line: usize) -> ObscuraResult<Filter> {
+    let (name, argument) = match text.split_once('(') {
+        Some((name, rest)) => {
+            let argument = rest
+                .strip_suffix(')')
+                .and_then(|inner| unquote(inner.trim()));
+            match argument {
+                Some(argument) => (name.trim(), Some(argument)),
+                None => {
+                    return Err(ObscuraError::CustomError(format!(
+                        "Invalid filter '{}' on line {}: expected a quoted argument",
+                        text, line
+                    )))
+                }
+            }
+        }
+        None => (text, None),
+    };
+
+    match (name, argument) {
+        ("base64", None) => Ok(Filter::Base64),
+        ("json-escape", None) => Ok(Filter::JsonEscape),
+        ("url-encode", None) => Ok(Filter::UrlEncode),
+        ("default", Some(argument)) => Ok(Filter::Default(argument)),
+        ("default", None) => Err(ObscuraError::CustomError(format!(
+            "Filter 'default' on line {} needs a value, e.g. default(\"none\")",
+            line
+        ))),
+        _ => Err(ObscuraError::CustomError(format!(
+            "Unknown filter '{}' on line {} (expected base64, json-escape, url-encode or default)",
+            text, line
+        ))),
+    }
+}
+
+fn unquote(text: &str) -> Option<String> {
+    let quote = text.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+    let inner = text.strip_prefix(quote)?.strip_suffix(quote)?;
+    let mut value = String::new();
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            value.push(chars.next()?);
+        } else {
+            value.push(c);
+        }
+    }
+    Some(value)
+}